@@ -1,6 +1,9 @@
 use crate::items::tokens::CommentToken;
 use crate::parse::TokenStream;
 use crate::span::{Position, Span};
+use std::collections::BTreeSet;
+
+mod edoc;
 
 /// A procedural macro to derive [Format].
 pub use efmt_derive::Format;
@@ -30,6 +33,381 @@ impl<A: Format, B: Format> Format for (A, B) {
     }
 }
 
+/// Options to customize the layout produced by [Formatter].
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    pub(crate) max_columns: usize,
+    pub(crate) compact_receive: bool,
+    pub(crate) compact_try: bool,
+    pub(crate) operator_break: OperatorBreak,
+    pub(crate) wrap_edoc: Option<usize>,
+    pub(crate) newline_between_functions: usize,
+    pub(crate) tab_width: usize,
+    pub(crate) remove_redundant_parens: bool,
+    pub(crate) collection_layout: CollectionLayout,
+    pub(crate) align_clause_arrows: bool,
+    pub(crate) align_map_values: bool,
+    pub(crate) force_vertical_depth: Option<usize>,
+    pub(crate) blank_line_between_clauses: bool,
+    pub(crate) blank_line_after_attributes: bool,
+    pub(crate) pack_collections: bool,
+    pub(crate) line_ending: LineEnding,
+}
+
+impl FormatOptions {
+    /// The default value of [FormatOptions::max_columns()].
+    pub const DEFAULT_MAX_COLUMNS: usize = 100;
+
+    /// The minimum value of [FormatOptions::max_columns()].
+    pub const MIN_MAX_COLUMNS: usize = 10;
+
+    /// The default value of [FormatOptions::newline_between_functions()].
+    pub const DEFAULT_NEWLINE_BETWEEN_FUNCTIONS: usize = 2;
+
+    /// The default value of [FormatOptions::tab_width()].
+    pub const DEFAULT_TAB_WIDTH: usize = 8;
+
+    /// Makes a [FormatOptions] instance with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the column budget used when deciding whether an item can be written in a single line (default: [FormatOptions::DEFAULT_MAX_COLUMNS]).
+    ///
+    /// Note that efmt never splits a line only because it exceeds this limit.
+    /// A value smaller than [FormatOptions::MIN_MAX_COLUMNS] is raised to it with a warning.
+    pub fn max_columns(mut self, n: usize) -> Self {
+        if n < Self::MIN_MAX_COLUMNS {
+            log::warn!(
+                "max_columns must be at least {} (got {n}), so {} is used instead.",
+                Self::MIN_MAX_COLUMNS,
+                Self::MIN_MAX_COLUMNS
+            );
+        }
+        self.max_columns = n.max(Self::MIN_MAX_COLUMNS);
+        self
+    }
+
+    /// Writes `receive` expressions that only have an `after` clause in a single line if they fit.
+    pub fn compact_receive(mut self, enabled: bool) -> Self {
+        self.compact_receive = enabled;
+        self
+    }
+
+    /// Writes `try ... of` expressions that have a single `of` clause and at most one `catch` clause
+    /// in a single line if they fit.
+    pub fn compact_try(mut self, enabled: bool) -> Self {
+        self.compact_try = enabled;
+        self
+    }
+
+    /// Sets where a line break is placed around a binary operator of a multi-line expression (default: [OperatorBreak::After]).
+    pub fn operator_break(mut self, operator_break: OperatorBreak) -> Self {
+        self.operator_break = operator_break;
+        self
+    }
+
+    /// Reflows the paragraphs of `@doc` comments so that each line fits in `max_columns`.
+    ///
+    /// Lines starting with another `@tag`, empty lines, indented lines and code blocks
+    /// (`<pre>` ... `</pre>` or fenced by `` ``` ``) are kept as-is.
+    /// By default, the text of comments is never changed.
+    pub fn wrap_edoc(mut self, max_columns: usize) -> Self {
+        self.wrap_edoc = Some(max_columns);
+        self
+    }
+
+    /// Sets the number of empty lines around a top-level function definition (default: [FormatOptions::DEFAULT_NEWLINE_BETWEEN_FUNCTIONS]).
+    ///
+    /// A `-spec` is treated as a part of the following function, i.e., no empty lines are inserted between them.
+    /// Note that, if this is `0`, a single empty line in the original text is still kept.
+    pub fn newline_between_functions(mut self, n: usize) -> Self {
+        self.newline_between_functions = n;
+        self
+    }
+
+    /// Sets the width of a tab stop used to compute the column after a tab character (default: [FormatOptions::DEFAULT_TAB_WIDTH]).
+    ///
+    /// Tabs between tokens are replaced with spaces, but tabs in comments, string literals and
+    /// unformatted regions are kept as-is, so the items aligned after them depend on this width.
+    /// `0` is treated as `1`.
+    pub fn tab_width(mut self, n: usize) -> Self {
+        self.tab_width = n.max(1);
+        self
+    }
+
+    /// Removes parentheses around an expression that is a whole element of a list, tuple or argument list, or a whole clause body expression (default: `false`).
+    ///
+    /// Parentheses in macro definitions and macro arguments are always kept.
+    pub fn remove_redundant_parens(mut self, enabled: bool) -> Self {
+        self.remove_redundant_parens = enabled;
+        self
+    }
+
+    /// Sets how a multi-line list, tuple or bitstring is laid out (default: [CollectionLayout::Aligned]).
+    pub fn collection_layout(mut self, layout: CollectionLayout) -> Self {
+        self.collection_layout = layout;
+        self
+    }
+
+    /// Pads the patterns of single-line clauses in a `case`, `receive`, `try ... of` or `maybe ... else`
+    /// so that their `->` start at the same column (default: `false`).
+    ///
+    /// Clauses that span multiple lines, and clauses whose `->` would exceed `max_columns` once aligned,
+    /// are formatted as usual.
+    pub fn align_clause_arrows(mut self, enabled: bool) -> Self {
+        self.align_clause_arrows = enabled;
+        self
+    }
+
+    /// Pads the keys of a map written one entry per line so that their `=>` and `:=` start at the same column (default: `false`).
+    ///
+    /// Entries that span multiple lines, and entries whose `=>` or `:=` would exceed `max_columns` once aligned,
+    /// are formatted as usual.
+    pub fn align_map_values(mut self, enabled: bool) -> Self {
+        self.align_map_values = enabled;
+        self
+    }
+
+    /// Inserts an empty line between the clauses of a multi-line function definition (default: `false`).
+    ///
+    /// The clauses of `fun`, `case`, `if`, `receive` and `try` are not affected.
+    pub fn blank_line_between_clauses(mut self, enabled: bool) -> Self {
+        self.blank_line_between_clauses = enabled;
+        self
+    }
+
+    /// Puts exactly one empty line between the leading attributes of a module (e.g., `-module`, `-export` and `-include`)
+    /// and the first function, spec or type declaration that follows them (default: `false`).
+    ///
+    /// This overrides [FormatOptions::newline_between_functions()] for the first function.
+    pub fn blank_line_after_attributes(mut self, enabled: bool) -> Self {
+        self.blank_line_after_attributes = enabled;
+        self
+    }
+
+    /// Keeps multiple primitive elements of a multi-line list, tuple, bitstring or record on the same line
+    /// if they share a line in the original text (default: `true`).
+    ///
+    /// If `false`, a multi-line collection is always written one element per line.
+    pub fn pack_collections(mut self, enabled: bool) -> Self {
+        self.pack_collections = enabled;
+        self
+    }
+
+    /// Sets the line ending of the formatted text (default: [LineEnding::Lf]).
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Writes a list, tuple or bitstring nested at depth `depth` or deeper one element per line even if it fits in a single line.
+    ///
+    /// The outermost collection is at depth 1, and the tag of a tagged tuple (e.g., `{ok, ...}`) stays on the first line.
+    /// The items containing a broken collection are laid out as if it were broken in the original text.
+    pub fn force_vertical_depth(mut self, depth: usize) -> Self {
+        self.force_vertical_depth = Some(depth);
+        self
+    }
+
+    /// Returns `true` if [FormatOptions::remove_redundant_parens()] is enabled.
+    pub fn removes_redundant_parens(&self) -> bool {
+        self.remove_redundant_parens
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            max_columns: Self::DEFAULT_MAX_COLUMNS,
+            compact_receive: false,
+            compact_try: false,
+            operator_break: OperatorBreak::After,
+            wrap_edoc: None,
+            newline_between_functions: Self::DEFAULT_NEWLINE_BETWEEN_FUNCTIONS,
+            tab_width: Self::DEFAULT_TAB_WIDTH,
+            remove_redundant_parens: false,
+            collection_layout: CollectionLayout::Aligned,
+            align_clause_arrows: false,
+            align_map_values: false,
+            force_vertical_depth: None,
+            blank_line_between_clauses: false,
+            blank_line_after_attributes: false,
+            pack_collections: true,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+/// Position of a line break around a binary operator.
+///
+/// Note that `=`, `?=` and `!` always break after the operator.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OperatorBreak {
+    /// Breaks before an operator (i.e., an operator is placed at the start of the next line).
+    Before,
+
+    /// Breaks after an operator (i.e., an operator is placed at the end of the line).
+    #[default]
+    After,
+}
+
+impl std::str::FromStr for OperatorBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "before" => Ok(Self::Before),
+            "after" => Ok(Self::After),
+            _ => Err(format!("unknown operator break position: {s:?}")),
+        }
+    }
+}
+
+impl std::fmt::Display for OperatorBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Before => write!(f, "before"),
+            Self::After => write!(f, "after"),
+        }
+    }
+}
+
+/// Layout of a multi-line list, tuple or bitstring.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionLayout {
+    /// Aligns the elements with the first one that is placed next to the opening bracket.
+    ///
+    /// ```erlang
+    /// [foo,
+    ///  bar]
+    /// ```
+    #[default]
+    Aligned,
+
+    /// Places each element on its own indented line and the closing bracket on its own line.
+    ///
+    /// ```erlang
+    /// [
+    ///     foo,
+    ///     bar
+    /// ]
+    /// ```
+    Hanging,
+}
+
+impl std::str::FromStr for CollectionLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "aligned" => Ok(Self::Aligned),
+            "hanging" => Ok(Self::Hanging),
+            _ => Err(format!("unknown collection layout: {s:?}")),
+        }
+    }
+}
+
+impl std::fmt::Display for CollectionLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Aligned => write!(f, "aligned"),
+            Self::Hanging => write!(f, "hanging"),
+        }
+    }
+}
+
+/// Line ending of a formatted text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`.
+    #[default]
+    Lf,
+
+    /// `\r\n`.
+    Crlf,
+
+    /// The dominant line ending of the original text.
+    ///
+    /// If the original text mixes line endings, it's normalized to the dominant one with a warning.
+    Auto,
+}
+
+impl LineEnding {
+    /// Returns the dominant line ending of `text` ([LineEnding::Lf] if there is a tie).
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+        if crlf > lf {
+            Self::Crlf
+        } else {
+            Self::Lf
+        }
+    }
+
+    fn apply(self, text: String, original: &str) -> String {
+        let newline = match self {
+            Self::Lf if !text.contains('\r') => return text,
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+            Self::Auto => {
+                let line_ending = Self::detect(original);
+                let crlf = original.matches("\r\n").count();
+                if crlf != 0 && crlf != original.matches('\n').count() {
+                    log::warn!("Found mixed line endings (normalized to {line_ending}).");
+                }
+                return line_ending.apply(text, original);
+            }
+        };
+
+        // Line breaks in string, character and quoted atom literals are kept as-is
+        // because they are a part of the values.
+        let normalize = |s: &str| s.replace("\r\n", "\n").replace('\n', newline);
+        let mut converted = String::with_capacity(text.len());
+        let mut layout = String::new();
+        for token in erl_tokenize::Tokenizer::new(&text) {
+            let Ok(token) = token else {
+                return normalize(&text);
+            };
+            if matches!(
+                token,
+                erl_tokenize::Token::Whitespace(_) | erl_tokenize::Token::Comment(_)
+            ) {
+                layout.push_str(token.text());
+            } else {
+                converted.push_str(&normalize(&layout));
+                layout.clear();
+                converted.push_str(token.text());
+            }
+        }
+        converted.push_str(&normalize(&layout));
+        converted
+    }
+}
+
+impl std::str::FromStr for LineEnding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lf" => Ok(Self::Lf),
+            "crlf" => Ok(Self::Crlf),
+            "auto" => Ok(Self::Auto),
+            _ => Err(format!("unknown line ending: {s:?}")),
+        }
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lf => write!(f, "lf"),
+            Self::Crlf => write!(f, "crlf"),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
+
 const EOF: Position = Position::new(usize::MAX, usize::MAX, usize::MAX);
 const EOF_MINUS_1: Position = Position::new(usize::MAX - 1, usize::MAX, usize::MAX);
 
@@ -46,10 +424,20 @@ pub struct Formatter {
     skipping: bool,
     pending_blank: Option<Blank>,
     is_last_macro: bool,
+    comment_lines: Vec<usize>,
+    skip_blank_line: bool,
+    keep_parens: bool,
+    collection_depth: usize,
+    forced_collections: BTreeSet<Position>,
+    options: FormatOptions,
 }
 
 impl Formatter {
     pub fn new(ts: TokenStream) -> Self {
+        Self::with_options(ts, FormatOptions::default())
+    }
+
+    pub fn with_options(ts: TokenStream, options: FormatOptions) -> Self {
         Self {
             ts,
             indent: 0,
@@ -62,12 +450,53 @@ impl Formatter {
             skipping: false,
             pending_blank: None,
             is_last_macro: false,
+            comment_lines: Vec::new(),
+            skip_blank_line: false,
+            keep_parens: false,
+            collection_depth: 0,
+            forced_collections: BTreeSet::new(),
+            options,
         }
     }
 
+    pub fn options(&self) -> &FormatOptions {
+        &self.options
+    }
+
+    /// Formats `item` as the whole text and returns the result (see [Formatter::finish()]).
+    pub fn format_all(mut self, item: &impl Format) -> String {
+        self.find_forced_collections(item);
+        item.format(&mut self);
+        self.finish()
+    }
+
     pub fn finish(mut self) -> String {
         self.write_macros_and_comments(EOF_MINUS_1);
-        self.buf
+        let text = if let Some(max_columns) = self.options.wrap_edoc {
+            self::edoc::wrap_doc_comments(&self.buf, &self.comment_lines, max_columns)
+        } else {
+            std::mem::take(&mut self.buf)
+        };
+        self.options.line_ending.apply(text, &self.ts.text())
+    }
+
+    /// Formats only `item` and returns the result with the byte range of the original text it replaces.
+    ///
+    /// Comments and macros before `item` are not written, but a trailing comment on its last line is.
+    pub fn format_item(mut self, item: &impl Format) -> (String, std::ops::Range<usize>) {
+        let start = item.start_position();
+        self.next_position = start;
+        self.find_forced_collections(item);
+        item.format(&mut self);
+
+        let range = start.offset()..self.next_position.offset();
+        self.buf.truncate(self.buf.trim_end().len());
+        let text = if let Some(max_columns) = self.options.wrap_edoc {
+            self::edoc::wrap_doc_comments(&self.buf, &self.comment_lines, max_columns)
+        } else {
+            std::mem::take(&mut self.buf)
+        };
+        (self.options.line_ending.apply(text, &self.ts.text()), range)
     }
 
     pub fn last_char(&self) -> Option<char> {
@@ -114,6 +543,14 @@ impl Formatter {
 
     pub fn has_newline_until(&self, next: &impl Span) -> bool {
         self.next_position.line() != next.start_position().line()
+            || self.contains_forced_collection(self.next_position, next.start_position())
+    }
+
+    /// Returns `true` if `span` contains a newline in the original text
+    /// or a collection written one element per line by [FormatOptions::force_vertical_depth()].
+    pub fn contains_newline(&self, span: &impl Span) -> bool {
+        span.contains_newline()
+            || self.contains_forced_collection(span.start_position(), span.end_position())
     }
 
     pub fn write_span(&mut self, span: &impl Span) {
@@ -126,6 +563,7 @@ impl Formatter {
         }
         self.skipping = false;
 
+        let skip_blank_line = std::mem::take(&mut self.skip_blank_line);
         self.with_multi_line_mode(|this| {
             match this.pending_blank.take() {
                 None => {}
@@ -133,7 +571,8 @@ impl Formatter {
                 Some(Blank::Newline(n)) => this.write_newlines(n),
             }
 
-            if this.next_position.line() + 1 < span.start_position().line()
+            if !skip_blank_line
+                && this.next_position.line() + 1 < span.start_position().line()
                 && this.is_single_blank_line()
             {
                 this.write_newlines(2);
@@ -155,11 +594,12 @@ impl Formatter {
 
         self.buf.push_str(text);
 
+        let tab_width = self.options.tab_width;
         for c in text.chars() {
-            if c == '\n' {
-                self.column = 0;
-            } else {
-                self.column += 1;
+            match c {
+                '\n' => self.column = 0,
+                '\t' => self.column += tab_width - self.column % tab_width,
+                _ => self.column += 1,
             }
         }
 
@@ -246,6 +686,60 @@ impl Formatter {
         }
     }
 
+    /// Executes `f` without removing any parentheses (e.g., in macro definitions where they can change the semantics).
+    pub(crate) fn with_parens_kept<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let keep_parens = std::mem::replace(&mut self.keep_parens, true);
+        f(self);
+        self.keep_parens = keep_parens;
+    }
+
+    pub(crate) fn is_paren_removal_allowed(&self) -> bool {
+        !self.keep_parens
+    }
+
+    /// Executes `f` as the formatting of a collection nested in the current one.
+    pub(crate) fn with_nested_collection<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.collection_depth += 1;
+        f(self);
+        self.collection_depth -= 1;
+    }
+
+    /// Returns `true` if the current collection has to be written one element per line
+    /// (see [FormatOptions::force_vertical_depth()]).
+    pub(crate) fn is_vertical_layout_forced(&mut self, collection: &impl Span) -> bool {
+        let forced = self
+            .options
+            .force_vertical_depth
+            .is_some_and(|depth| self.collection_depth >= depth);
+        if forced {
+            self.forced_collections.insert(collection.start_position());
+        }
+        forced
+    }
+
+    // The depth of a collection is only known when it's formatted,
+    // so a dry run finds the forced collections before the items containing them are laid out.
+    fn find_forced_collections(&mut self, item: &impl Format) {
+        if self.options.force_vertical_depth.is_some() {
+            self.write_and_discard(|this| item.format(this));
+        }
+    }
+
+    fn contains_forced_collection(&self, start: Position, end: Position) -> bool {
+        start < end && self.forced_collections.range(start..end).next().is_some()
+    }
+
+    /// Drops the empty lines of the original text before the next span.
+    pub(crate) fn skip_next_blank_line(&mut self) {
+        self.skip_blank_line = true;
+    }
+
     pub fn set_next_comment_indent(&mut self, n: usize) {
         self.next_comment_indent = Some(n);
     }
@@ -271,7 +765,104 @@ impl Formatter {
         self.single_line_mode = mode;
     }
 
-    fn with_multi_line_mode<F>(&mut self, f: F)
+    /// Executes `f` with a local column budget that overrides [FormatOptions::max_columns()].
+    ///
+    /// The budget only affects the checks made by [Formatter::fits_in_single_line()] within `f`
+    /// (the indentation is unchanged), and the inherited value is restored after `f` returns.
+    /// Note that the budget is an absolute column, not a width relative to the current indentation.
+    pub fn with_max_columns<F>(&mut self, max_columns: usize, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let inherited = std::mem::replace(&mut self.options.max_columns, max_columns);
+        f(self);
+        self.options.max_columns = inherited;
+    }
+
+    /// Returns `true` if `f` writes no newlines and the result fits in `max_columns`.
+    ///
+    /// The text written by `f` is discarded.
+    pub fn fits_in_single_line<F>(&mut self, f: F) -> bool
+    where
+        F: FnOnce(&mut Self),
+    {
+        let column = self.column;
+        self.measure(f)
+            .is_some_and(|width| column + width <= self.options.max_columns)
+    }
+
+    /// Returns the width of the text written by `f` if it contains no newlines.
+    ///
+    /// The text written by `f` is discarded, so this can be used to decide the layout of an item
+    /// before actually writing it.
+    pub fn measure<F>(&mut self, f: F) -> Option<usize>
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.write_and_discard(|this| {
+            let column = this.column;
+            f(this);
+            this.column.saturating_sub(column)
+        })
+    }
+
+    /// Returns the width of the text written by `f` if it contains no newlines.
+    ///
+    /// `f` is executed as if the source text before `start` had already been written,
+    /// so that an item that follows the current position can be measured in advance.
+    /// The text written by `f` is discarded.
+    pub(crate) fn single_line_width<F>(&mut self, start: Position, f: F) -> Option<usize>
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.measure(|this| {
+            this.next_position = start;
+            this.skipping = false;
+            this.pending_blank = None;
+            f(this);
+        })
+    }
+
+    fn write_and_discard<F, T>(&mut self, f: F) -> Option<T>
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        let buf_len = self.buf.len();
+        let indent = self.indent;
+        let next_comment_indent = self.next_comment_indent;
+        let column = self.column;
+        let next_position = self.next_position;
+        let single_line_mode = self.single_line_mode;
+        let last_comment_or_macro_position = self.last_comment_or_macro_position;
+        let skipping = self.skipping;
+        let pending_blank = self.pending_blank;
+        let is_last_macro = self.is_last_macro;
+        let comment_lines = self.comment_lines.len();
+        let skip_blank_line = self.skip_blank_line;
+
+        let value = f(self);
+        let value = self
+            .buf
+            .get(buf_len..)
+            .is_some_and(|s| !s.contains('\n'))
+            .then_some(value);
+
+        self.buf.truncate(buf_len);
+        self.indent = indent;
+        self.next_comment_indent = next_comment_indent;
+        self.column = column;
+        self.next_position = next_position;
+        self.single_line_mode = single_line_mode;
+        self.last_comment_or_macro_position = last_comment_or_macro_position;
+        self.skipping = skipping;
+        self.pending_blank = pending_blank;
+        self.is_last_macro = is_last_macro;
+        self.comment_lines.truncate(comment_lines);
+        self.skip_blank_line = skip_blank_line;
+        value
+    }
+
+    pub(crate) fn with_multi_line_mode<F>(&mut self, f: F)
     where
         F: FnOnce(&mut Self),
     {
@@ -341,6 +932,9 @@ impl Formatter {
             }
 
             self.write_span(comment);
+            if self.options.wrap_edoc.is_some() && !comment.is_trailing() {
+                self.comment_lines.push(self.buf.matches('\n').count());
+            }
             self.write_newline();
         }
 
@@ -437,7 +1031,7 @@ impl std::str::FromStr for Directive {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum Blank {
     Space(usize),
     Newline(usize),
@@ -445,6 +1039,7 @@ enum Blank {
 
 #[cfg(test)]
 mod tests {
+    use super::Format as _;
     use crate::items::Module;
 
     #[test]
@@ -517,6 +1112,134 @@ mod tests {
         }
     }
 
+    #[test]
+    fn wrap_edoc_works() {
+        let text = indoc::indoc! {"
+            %% @doc Returns the sum of the given integers if all of them are non-negative.
+            %% @param Xs is a list of integers
+            -spec sum([integer()]) -> integer().
+            sum(Xs) ->
+                %% @doc This comment is not at the top level but is reflowed too.
+                lists:sum(Xs).  % @doc Trailing comments are never reflowed.
+            "};
+        let expected = indoc::indoc! {"
+            %% @doc Returns the sum of the given integers if
+            %% all of them are non-negative.
+            %% @param Xs is a list of integers
+            -spec sum([integer()]) -> integer().
+            sum(Xs) ->
+                %% @doc This comment is not at the top level
+                %% but is reflowed too.
+                lists:sum(Xs).  % @doc Trailing comments are never reflowed.
+            "};
+        let options = crate::format::FormatOptions::new().wrap_edoc(50);
+        crate::assert_format!(text, expected, Module, options);
+        crate::assert_format!(expected, expected, Module, options);
+        crate::assert_format!(text, Module);
+    }
+
+    #[test]
+    fn with_max_columns_works() {
+        let text = "foo(bar, baz)";
+        let mut ts = crate::parse::TokenStream::new(erl_tokenize::Tokenizer::new(text.to_owned()));
+        let expr: crate::items::Expr = ts.parse().unwrap();
+        let mut fmt = super::Formatter::new(ts);
+
+        assert!(fmt.fits_in_single_line(|fmt| expr.format(fmt)));
+        fmt.with_max_columns(10, |fmt| {
+            assert!(!fmt.fits_in_single_line(|fmt| expr.format(fmt)));
+            fmt.with_max_columns(20, |fmt| {
+                assert!(fmt.fits_in_single_line(|fmt| expr.format(fmt)));
+            });
+            assert_eq!(fmt.options().max_columns, 10);
+        });
+        assert_eq!(
+            fmt.options().max_columns,
+            super::FormatOptions::DEFAULT_MAX_COLUMNS
+        );
+    }
+
+    #[test]
+    fn min_max_columns_works() {
+        let min = super::FormatOptions::MIN_MAX_COLUMNS;
+        for n in [0, 1, 5] {
+            let options = super::FormatOptions::new().max_columns(n);
+            assert_eq!(options.max_columns, min);
+
+            let text = "foo(Bar, [1, 2, 3], #{a => b})";
+            crate::assert_format!(text, text, crate::items::Expr, options);
+        }
+        assert_eq!(
+            super::FormatOptions::new().max_columns(min + 1).max_columns,
+            min + 1
+        );
+    }
+
+    #[test]
+    fn line_ending_works() {
+        use super::{FormatOptions, LineEnding};
+
+        let lf = "-module(foo).\n\n\n%% comment\nfoo() ->\n    \"a\nb\".\n";
+        let crlf = "-module(foo).\r\n\r\n\r\n%% comment\r\nfoo() ->\r\n    \"a\nb\".\r\n";
+        let mixed = "-module(foo).\r\n\r\n\r\n%% comment\r\nfoo()->\n    \"a\nb\".\r\n";
+        let format = |text: &str, line_ending| {
+            let options = FormatOptions::new().line_ending(line_ending);
+            crate::format_text_with_options::<Module>(text, &options).unwrap()
+        };
+
+        // Line breaks in string literals are kept as-is.
+        for text in [lf, crlf, mixed] {
+            similar_asserts::assert_eq!(format(text, LineEnding::Lf), lf);
+            similar_asserts::assert_eq!(format(text, LineEnding::Crlf), crlf);
+        }
+        similar_asserts::assert_eq!(format(lf, LineEnding::Auto), lf);
+        similar_asserts::assert_eq!(format(crlf, LineEnding::Auto), crlf);
+
+        // Mixed line endings are normalized to the dominant one.
+        similar_asserts::assert_eq!(format(mixed, LineEnding::Auto), crlf);
+        assert_eq!(LineEnding::detect(mixed), LineEnding::Crlf);
+        assert_eq!(LineEnding::detect("a\r\nb\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn tab_works() {
+        // Tabs between tokens are replaced with spaces.
+        let text = "foo(A,\tB) ->\n\tX =\tbar(A,\n\t\t\tB),\t% comment\n\tX.";
+        let expected = indoc::indoc! {"
+            foo(A, B) ->
+                X = bar(A,
+                        B),  % comment
+                X."};
+        crate::assert_format!(text, expected, crate::items::forms::Form);
+
+        // Tabs in string literals advance the column to the next tab stop.
+        let text = "X = \"abc\td\" ++ [x,\ny]";
+        let expected = format!("X = \"abc\td\" ++ [x,\n{}y]", " ".repeat(23));
+        crate::assert_format!(text, expected, crate::items::Expr);
+
+        let options = super::FormatOptions::new().tab_width(4);
+        let expected = format!("X = \"abc\td\" ++ [x,\n{}y]", " ".repeat(19));
+        crate::assert_format!(text, expected, crate::items::Expr, options);
+    }
+
+    #[test]
+    fn measure_works() {
+        let text = "foo(bar, baz)\n[1,\n 2]";
+        let mut ts = crate::parse::TokenStream::new(erl_tokenize::Tokenizer::new(text.to_owned()));
+        let single_line: crate::items::Expr = ts.parse().unwrap();
+        let multi_line: crate::items::Expr = ts.parse().unwrap();
+        let mut fmt = super::Formatter::new(ts);
+
+        fmt.write_spaces(4);
+        assert_eq!(fmt.measure(|fmt| single_line.format(fmt)), Some(13));
+        assert_eq!(fmt.measure(|fmt| multi_line.format(fmt)), None);
+        assert_eq!(fmt.column(), 4);
+
+        single_line.format(&mut fmt);
+        assert_eq!(fmt.column(), 4 + 13);
+    }
+
     #[test]
     fn directives_works() {
         let texts = [(
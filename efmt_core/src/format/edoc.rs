@@ -0,0 +1,190 @@
+//! Reflowing of edoc `@doc` comments (see [FormatOptions::wrap_edoc()](super::FormatOptions::wrap_edoc)).
+use std::collections::HashSet;
+
+/// Reflows the paragraphs of `@doc` comments in `text` so that each line fits in `max_columns`.
+///
+/// `comment_lines` are the (zero-origin) indices of the lines that consist of a single line comment.
+/// Only those lines are touched.
+pub(crate) fn wrap_doc_comments(text: &str, comment_lines: &[usize], max_columns: usize) -> String {
+    let comment_lines = comment_lines.iter().copied().collect::<HashSet<_>>();
+    let lines = text.split('\n').collect::<Vec<_>>();
+    let comments = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if comment_lines.contains(&i) {
+                CommentLine::parse(line)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut output = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(head) = comments[i].filter(|c| c.is_doc_tag()) else {
+            output.push(lines[i].to_owned());
+            i += 1;
+            continue;
+        };
+
+        let mut end = i + 1;
+        while let Some(c) = comments.get(end).copied().flatten() {
+            if c.indent != head.indent || c.prefix != head.prefix || c.body.starts_with('@') {
+                break;
+            }
+            end += 1;
+        }
+        wrap_doc(&comments[i..end], &lines[i..end], max_columns, &mut output);
+        i = end;
+    }
+    output.join("\n")
+}
+
+fn wrap_doc(
+    comments: &[Option<CommentLine>],
+    lines: &[&str],
+    max_columns: usize,
+    output: &mut Vec<String>,
+) {
+    let head = comments[0].expect("unreachable");
+    let mut paragraph = Vec::new();
+    let mut in_code = false;
+    for (comment, line) in comments.iter().zip(lines) {
+        let comment = comment.expect("unreachable");
+        let body = comment.body;
+        let is_code_delimiter = body.contains("<pre>")
+            || body.contains("</pre>")
+            || body.trim_start().starts_with("```");
+        if in_code || is_code_delimiter || !comment.is_plain_text() || body == "@doc" {
+            flush_paragraph(&head, &mut paragraph, max_columns, output);
+            output.push((*line).to_owned());
+            if is_code_delimiter {
+                in_code = if body.trim_start().starts_with("```") {
+                    !in_code
+                } else {
+                    body.rfind("<pre>") > body.rfind("</pre>")
+                };
+            }
+        } else {
+            paragraph.extend(body.split_whitespace());
+        }
+    }
+    flush_paragraph(&head, &mut paragraph, max_columns, output);
+}
+
+fn flush_paragraph(
+    head: &CommentLine,
+    words: &mut Vec<&str>,
+    max_columns: usize,
+    output: &mut Vec<String>,
+) {
+    let prefix = format!("{}{} ", " ".repeat(head.indent), head.prefix);
+    let mut line = String::new();
+    for word in words.drain(..) {
+        if !line.is_empty() && (prefix.len() + line.len() + 1 + word.chars().count()) > max_columns
+        {
+            output.push(format!("{prefix}{line}"));
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        output.push(format!("{prefix}{line}"));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CommentLine<'a> {
+    indent: usize,
+    prefix: &'a str,
+    body: &'a str,
+}
+
+impl<'a> CommentLine<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let rest = line.trim_start_matches(' ');
+        let indent = line.len() - rest.len();
+        let body = rest.trim_start_matches('%');
+        let prefix = &rest[..rest.len() - body.len()];
+        if prefix.is_empty() {
+            return None;
+        }
+        let body = if body.is_empty() {
+            body
+        } else {
+            body.strip_prefix(' ')?
+        };
+        Some(Self {
+            indent,
+            prefix,
+            body,
+        })
+    }
+
+    fn is_doc_tag(&self) -> bool {
+        self.body == "@doc" || self.body.starts_with("@doc ")
+    }
+
+    // Empty lines, indented lines (e.g., code examples), list items and HTML tags are kept as-is.
+    fn is_plain_text(&self) -> bool {
+        !self.body.is_empty()
+            && !self.body.starts_with(char::is_whitespace)
+            && !self.body.starts_with(['-', '*', '<'])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrap(text: &str, max_columns: usize) -> String {
+        let comment_lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.trim_start().starts_with('%'))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        wrap_doc_comments(text, &comment_lines, max_columns)
+    }
+
+    #[test]
+    fn wrap_doc_comments_works() {
+        let text = indoc::indoc! {"
+            %% @doc This is a long paragraph
+            %% that should be reflowed to fit.
+            %%
+            %% Second paragraph.
+            %% @param Foo is not touched even if it is too long
+            foo(Foo) -> Foo."};
+        let expected = indoc::indoc! {"
+            %% @doc This is a long
+            %% paragraph that should be
+            %% reflowed to fit.
+            %%
+            %% Second paragraph.
+            %% @param Foo is not touched even if it is too long
+            foo(Foo) -> Foo."};
+        similar_asserts::assert_eq!(wrap(text, 28), expected);
+        similar_asserts::assert_eq!(wrap(expected, 28), expected);
+    }
+
+    #[test]
+    fn code_blocks_are_not_wrapped() {
+        let text = indoc::indoc! {"
+            %% @doc
+            %% Example:
+            %% <pre>
+            %% foo(1, 2, 3, 4, 5, 6, 7, 8, 9).
+            %% </pre>
+            %%    indented(code, is, kept, as, is).
+            %% ```
+            %% bar(1, 2, 3, 4, 5, 6, 7, 8, 9).
+            %% ```"};
+        similar_asserts::assert_eq!(wrap(text, 20), text);
+    }
+}
@@ -3,8 +3,9 @@ use self::tokens::VariableToken;
 use crate::format::{Format, Formatter};
 use crate::items::components::Element;
 use crate::items::expressions::{BaseExpr, FullExpr, ListExpr, LiteralExpr};
+use crate::items::symbols::{CommaSymbol, DotSymbol};
 use crate::items::tokens::AtomToken;
-use crate::parse::Parse;
+use crate::parse::{self, Parse, TokenStream};
 use crate::span::Span;
 
 pub mod expressions;
@@ -24,7 +25,7 @@ mod module;
 
 pub use self::config::Config;
 pub use self::macros::Macro;
-pub use self::module::Module;
+pub use self::module::{Dependencies, Module};
 pub use crate::items::components::Either;
 pub use crate::items::components::Maybe;
 
@@ -49,6 +50,21 @@ impl Form {
     pub(crate) fn is_func_decl(&self) -> bool {
         matches!(self.0, self::forms::Form::FunDecl(_))
     }
+
+    /// Returns the name and arity of the function if this is a function spec or declaration.
+    pub fn func_name_and_arity(&self) -> Option<(&str, usize)> {
+        match &self.0 {
+            self::forms::Form::FunSpec(x) => Some((
+                x.function_name().value(),
+                x.clauses().next()?.params().len(),
+            )),
+            self::forms::Form::FunDecl(x) => {
+                let clause = x.clauses().next()?;
+                Some((clause.function_name().value(), clause.params().len()))
+            }
+            _ => None,
+        }
+    }
 }
 
 /// One of [types].
@@ -138,3 +154,32 @@ impl Expr {
         }
     }
 }
+
+/// [Expr] (`.` | `,`)?
+///
+/// An expression snippet such as an input of the Erlang shell.
+/// Unlike [Form], the trailing terminator is optional and it is preserved as-is if exists.
+/// Any tokens after the terminator are rejected.
+#[derive(Debug, Clone, Span, Format)]
+pub struct ExprSnippet {
+    expr: Expr,
+    terminator: Maybe<Either<DotSymbol, CommaSymbol>>,
+}
+
+impl ExprSnippet {
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
+
+impl Parse for ExprSnippet {
+    fn parse(ts: &mut TokenStream) -> parse::Result<Self> {
+        let expr = ts.parse()?;
+        let terminator = ts.parse()?;
+        if !ts.is_eof()? {
+            let token = ts.parse()?;
+            return Err(parse::Error::unexpected_token(ts, token));
+        }
+        Ok(Self { expr, terminator })
+    }
+}
@@ -43,6 +43,10 @@ impl_parse!(TypeAtom, "type");
 pub struct OpaqueAtom(AtomToken);
 impl_parse!(OpaqueAtom, "opaque");
 
+#[derive(Debug, Clone, Span, Format, Element)]
+pub struct NominalAtom(AtomToken);
+impl_parse!(NominalAtom, "nominal");
+
 #[derive(Debug, Clone, Span, Format, Element)]
 pub struct CallbackAtom(AtomToken);
 impl_parse!(CallbackAtom, "callback");
@@ -1,4 +1,4 @@
-use crate::format::{Format, Formatter};
+use crate::format::{CollectionLayout, Format, Formatter};
 use crate::items::keywords::WhenKeyword;
 use crate::items::symbols::{
     CloseBraceSymbol, CloseParenSymbol, CloseSquareSymbol, CommaSymbol, DoubleLeftAngleSymbol,
@@ -90,7 +90,7 @@ impl<T> Maybe<T> {
 
 impl<T: Element> Element for Maybe<T> {
     fn is_packable(&self) -> bool {
-        self.get().map_or(true, Element::is_packable)
+        self.get().is_none_or(Element::is_packable)
     }
 }
 
@@ -214,7 +214,7 @@ impl<T: Parse, D: Parse> Parse for NonEmptyItems<T, D> {
 
 impl<T: Format, D: Format> Format for NonEmptyItems<T, D> {
     fn format(&self, fmt: &mut Formatter) {
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             self.format_items(fmt);
         } else {
             fmt.with_single_line_mode(|fmt| {
@@ -251,6 +251,36 @@ fn format_non_empty_items<T, D>(
     });
 }
 
+fn is_hanging_collection(fmt: &Formatter, collection: &impl Span) -> bool {
+    fmt.options().collection_layout == CollectionLayout::Hanging && fmt.contains_newline(collection)
+}
+
+// Returns `true` if a collection of `len` elements has to be written one element per line
+// regardless of the original text.
+fn is_vertical_layout_forced(fmt: &mut Formatter, collection: &impl Span, len: usize) -> bool {
+    len > 1 && fmt.is_vertical_layout_forced(collection)
+}
+
+// Writes the items on their own lines indented by 4 from the current indent.
+// The closing bracket that follows is placed on a new line at the current indent.
+fn format_hanging_items<T, D>(
+    fmt: &mut Formatter,
+    items: impl Iterator<Item = T>,
+    delimiters: impl Iterator<Item = D>,
+) where
+    T: Format,
+    D: Format,
+{
+    let indent = fmt.indent() + 4;
+    fmt.with_scoped_indent(|fmt| {
+        fmt.set_indent(indent);
+        fmt.write_newline();
+        format_non_empty_items(fmt, items, delimiters);
+        fmt.set_next_comment_indent(indent);
+    });
+    fmt.write_newline();
+}
+
 #[derive(Debug, Clone, Span, Parse, Format)]
 pub struct Items<T, D = CommaSymbol>(Maybe<NonEmptyItems<T, D>>);
 
@@ -286,6 +316,12 @@ impl<T, D> MaybePackedItems<T, D> {
 }
 
 impl<T: Format, D: Format> MaybePackedItems<T, D> {
+    fn vertical_format(&self, fmt: &mut Formatter) {
+        fmt.with_multi_line_mode(|fmt| {
+            format_non_empty_items(fmt, self.items().iter(), self.delimiters().iter());
+        });
+    }
+
     fn packed_format(&self, fmt: &mut Formatter) {
         fmt.with_scoped_indent(|fmt| {
             fmt.set_indent(fmt.column());
@@ -314,7 +350,8 @@ impl<T: Format, D: Format> MaybePackedItems<T, D> {
 impl<T: Format + Element, D: Format> Format for MaybePackedItems<T, D> {
     fn format(&self, fmt: &mut Formatter) {
         if self.0.items().is_empty() {
-        } else if self.0.items().iter().all(Element::is_packable) {
+        } else if fmt.options().pack_collections && self.0.items().iter().all(Element::is_packable)
+        {
             fmt.with_scoped_indent(|fmt| {
                 fmt.set_indent(fmt.column());
                 self.packed_format(fmt);
@@ -345,8 +382,21 @@ impl<T: Element, D> ListLike<T, D> {
 impl<T: Element + Format, D: Format> Format for ListLike<T, D> {
     fn format(&self, fmt: &mut Formatter) {
         self.open.format(fmt);
-        self.items.format(fmt);
-        fmt.set_next_comment_indent(fmt.indent() + 1);
+        fmt.with_nested_collection(|fmt| {
+            let forced = is_vertical_layout_forced(fmt, self, self.items().len());
+            if !self.items().is_empty() && is_hanging_collection(fmt, self) {
+                fmt.with_multi_line_mode(|fmt| {
+                    format_hanging_items(fmt, self.items().iter(), self.items.delimiters().iter());
+                });
+            } else {
+                if forced {
+                    self.items.vertical_format(fmt);
+                } else {
+                    self.items.format(fmt);
+                }
+                fmt.set_next_comment_indent(fmt.indent() + 1);
+            }
+        });
         self.close.format(fmt);
     }
 }
@@ -395,6 +445,31 @@ impl<T: Element + Format> TupleLike<T> {
 impl<T: Element + Format> Format for TupleLike<T> {
     fn format(&self, fmt: &mut Formatter) {
         self.open.format(fmt);
+        fmt.with_nested_collection(|fmt| self.format_items(fmt));
+        self.close.format(fmt);
+    }
+}
+
+impl<T: Element + Format> TupleLike<T> {
+    fn format_items(&self, fmt: &mut Formatter) {
+        let forced = is_vertical_layout_forced(fmt, self, self.items.items().len());
+        if (self.tag.get().is_some() || !self.items.items().is_empty())
+            && is_hanging_collection(fmt, self)
+        {
+            let tag = self.tag.get();
+            fmt.with_multi_line_mode(|fmt| {
+                format_hanging_items(
+                    fmt,
+                    tag.map(|x| Either::A(&x.0))
+                        .into_iter()
+                        .chain(self.items.items().iter().map(Either::B)),
+                    tag.map(|x| Either::A(&x.1))
+                        .into_iter()
+                        .chain(self.items.delimiters().iter().map(Either::B)),
+                );
+            });
+            return;
+        }
         if let Some(tag) = self.tag.get() {
             if fmt.has_newline_until(&self.items) {
                 // Not a tagged tuple
@@ -407,7 +482,6 @@ impl<T: Element + Format> Format for TupleLike<T> {
                         .chain(self.items.delimiters().iter().map(Either::B)),
                 );
                 fmt.set_next_comment_indent(items_indent);
-                self.close.format(fmt);
                 return;
             }
 
@@ -416,9 +490,12 @@ impl<T: Element + Format> Format for TupleLike<T> {
         }
 
         let items_indent = fmt.column();
-        self.items.format(fmt);
+        if forced {
+            self.items.vertical_format(fmt);
+        } else {
+            self.items.format(fmt);
+        }
         fmt.set_next_comment_indent(items_indent);
-        self.close.format(fmt);
     }
 }
 
@@ -438,17 +515,86 @@ impl<T: Element> BitstringLike<T> {
 impl<T: Element + Format> Format for BitstringLike<T> {
     fn format(&self, fmt: &mut Formatter) {
         self.open.format(fmt);
-        self.items.format(fmt);
-        fmt.set_next_comment_indent(fmt.indent() + 1);
+        fmt.with_nested_collection(|fmt| {
+            let forced = is_vertical_layout_forced(fmt, self, self.items().len());
+            if !self.items().is_empty() && is_hanging_collection(fmt, self) {
+                fmt.with_multi_line_mode(|fmt| {
+                    format_hanging_items(fmt, self.items().iter(), self.items.delimiters().iter());
+                });
+            } else {
+                if forced {
+                    self.items.vertical_format(fmt);
+                } else {
+                    self.items.format(fmt);
+                }
+                fmt.set_next_comment_indent(fmt.indent() + 1);
+            }
+        });
         self.close.format(fmt);
     }
 }
 
-#[derive(Debug, Clone, Span, Parse, Format)]
+#[derive(Debug, Clone, Span, Parse)]
 pub struct MapLike<Prefix, Item> {
     inner: RecordLike<Prefix, MapItem<Item>>,
 }
 
+impl<Prefix: Format, Item: Format + Element> Format for MapLike<Prefix, Item> {
+    fn format(&self, fmt: &mut Formatter) {
+        let Some(delimiter_column) = self.delimiter_column(fmt) else {
+            self.inner.format(fmt);
+            return;
+        };
+
+        fmt.with_scoped_indent(|fmt| {
+            fmt.set_indent(fmt.column());
+            self.inner.prefix.format(fmt);
+            self.inner.fields.format_with(fmt, |fmt, item| {
+                item.format_with_delimiter_column(fmt, Some(delimiter_column));
+            });
+        });
+    }
+}
+
+impl<Prefix, Item: Format + Element> MapLike<Prefix, Item> {
+    // Returns the column at which the `=>` and `:=` of single-line entries are aligned
+    // if [FormatOptions::align_map_values()](crate::format::FormatOptions::align_map_values) is enabled
+    // and the map is written one entry per line.
+    fn delimiter_column(&self, fmt: &mut Formatter) -> Option<usize> {
+        let entries = self.inner.fields.get();
+        if !fmt.options().align_map_values || !fmt.contains_newline(&self.inner.fields) {
+            return None;
+        }
+        let one_entry_per_line = entries.windows(2).all(|pair| {
+            pair[0].end_position().line() != pair[1].start_position().line()
+                || !pair[0].is_packable()
+                || !pair[1].is_packable()
+                || !fmt.options().pack_collections
+        });
+        if !one_entry_per_line {
+            return None;
+        }
+
+        // See [RecordFieldsLike::format()] for the indentation of the entries.
+        let start = fmt.column() + 2;
+        let max_column = fmt.options().max_columns.saturating_sub(" => ".len());
+        let widths = entries
+            .iter()
+            .filter_map(|entry| {
+                if fmt.contains_newline(entry) {
+                    return None;
+                }
+                fmt.single_line_width(entry.key.start_position(), |fmt| entry.key.format(fmt))
+            })
+            .filter(|width| start + width <= max_column)
+            .collect::<Vec<_>>();
+        if widths.len() < 2 {
+            return None;
+        }
+        widths.into_iter().max().map(|width| start + width)
+    }
+}
+
 impl<Prefix, Item> MapLike<Prefix, Item> {
     pub fn new(prefix: Prefix, items: RecordFieldsLike<MapItem<Item>>) -> Self {
         Self {
@@ -477,19 +623,30 @@ pub struct MapItem<T> {
     value: T,
 }
 
-impl<T> Element for MapItem<T> {
+impl<T: Element> Element for MapItem<T> {
     fn is_packable(&self) -> bool {
-        false
+        self.key.is_packable() && self.value.is_packable()
     }
 }
 
 impl<T: Format> Format for MapItem<T> {
     fn format(&self, fmt: &mut Formatter) {
+        self.format_with_delimiter_column(fmt, None);
+    }
+}
+
+impl<T: Format> MapItem<T> {
+    fn format_with_delimiter_column(&self, fmt: &mut Formatter, delimiter_column: Option<usize>) {
         fmt.with_scoped_indent(|fmt| {
             self.key.format(fmt);
 
             let multiline = fmt.has_newline_until(&self.value);
-            fmt.write_space();
+            match delimiter_column {
+                Some(column) if !fmt.contains_newline(self) && fmt.column() < column => {
+                    fmt.write_spaces(column + 1 - fmt.column());
+                }
+                _ => fmt.write_space(),
+            }
             self.delimiter.format(fmt);
 
             if multiline {
@@ -550,9 +707,41 @@ impl<T> RecordFieldsLike<T> {
     }
 }
 
-impl<T: Format> Format for RecordFieldsLike<T> {
-    fn format(&self, fmt: &mut Formatter) {
-        let multiline = self.contains_newline();
+impl<T: Format + Element> RecordFieldsLike<T> {
+    // Packable fields that share a line in the original text are kept on the same line
+    // (unless [FormatOptions::pack_collections()](crate::format::FormatOptions::pack_collections) is disabled).
+    // A non-packable field always starts a new line and is followed by a line break.
+    fn format_multiline_fields<F>(&self, fmt: &mut Formatter, format_field: &F)
+    where
+        F: Fn(&mut Formatter, &T),
+    {
+        fmt.with_scoped_indent(|fmt| {
+            fmt.set_indent(fmt.column());
+            let fields = self.fields.items();
+            format_field(fmt, &fields[0]);
+            for (pair, delimiter) in fields.windows(2).zip(self.fields.delimiters().iter()) {
+                let (prev, field) = (&pair[0], &pair[1]);
+                let newline = !fmt.options().pack_collections
+                    || fmt.has_newline_until(field)
+                    || !prev.is_packable()
+                    || !field.is_packable();
+                delimiter.format(fmt);
+                if newline {
+                    fmt.write_newline();
+                } else {
+                    fmt.write_space();
+                }
+                format_field(fmt, field);
+            }
+        });
+    }
+
+    fn format_with<F>(&self, fmt: &mut Formatter, format_field: F)
+    where
+        F: Fn(&mut Formatter, &T),
+    {
+        let multiline = fmt.contains_newline(self)
+            && (!self.fields.items().is_empty() || fmt.token_stream().contains_comment(self));
         fmt.with_scoped_indent(|fmt| {
             let base_indent = fmt.indent();
             self.open.format(fmt);
@@ -560,8 +749,10 @@ impl<T: Format> Format for RecordFieldsLike<T> {
                 if multiline {
                     fmt.set_indent(base_indent + 2);
                     fmt.write_newline();
+                    self.format_multiline_fields(fmt, &format_field);
+                } else {
+                    self.fields.format(fmt);
                 }
-                self.fields.format(fmt);
             }
 
             if multiline {
@@ -575,6 +766,12 @@ impl<T: Format> Format for RecordFieldsLike<T> {
     }
 }
 
+impl<T: Format + Element> Format for RecordFieldsLike<T> {
+    fn format(&self, fmt: &mut Formatter) {
+        self.format_with(fmt, |fmt, field| field.format(fmt));
+    }
+}
+
 #[derive(Debug, Clone, Span, Parse, Format)]
 pub struct Clauses<T>(NonEmptyItems<T, SemicolonSymbol>);
 
@@ -582,6 +779,10 @@ impl<T> Clauses<T> {
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.0.items().iter()
     }
+
+    pub fn delimiters(&self) -> &[SemicolonSymbol] {
+        self.0.delimiters()
+    }
 }
 
 #[derive(Debug, Clone, Span, Parse)]
@@ -35,9 +35,12 @@ impl Parse for Config {
     fn parse(ts: &mut TokenStream) -> parse::Result<Self> {
         let sof = ts.prev_token_end_position();
         let mut terms = Vec::new();
-        while !ts.is_eof()? {
-            terms.push(ts.parse()?);
-        }
+        ts.with_term_mode_enabled(|ts| {
+            while !ts.is_eof()? {
+                terms.push(ts.parse()?);
+            }
+            Ok(())
+        })?;
         let eof = ts.next_token_start_position()?;
         Ok(Self { sof, terms, eof })
     }
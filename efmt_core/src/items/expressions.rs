@@ -1,7 +1,7 @@
 //! Erlang expressions.
-use crate::format::Format;
+use crate::format::{Format, Formatter};
 use crate::items::components::{Either, Element, Parenthesized};
-use crate::items::symbols::OpenBraceSymbol;
+use crate::items::symbols::{LessSymbol, OpenBraceSymbol};
 use crate::items::tokens::{
     AtomToken, CharToken, FloatToken, IntegerToken, LexicalToken, SigilStringToken, SymbolToken,
     VariableToken,
@@ -19,6 +19,7 @@ mod calls;
 mod functions;
 mod lists;
 mod maps;
+mod opaque;
 mod records;
 mod strings;
 mod tuples;
@@ -33,12 +34,86 @@ pub use self::lists::{
     ImproperListConstructExpr, ListComprehensionExpr, ListConstructExpr, ListExpr,
 };
 pub use self::maps::{MapExpr, MapUpdateExpr};
+pub use self::opaque::OpaqueTermExpr;
 pub use self::records::{RecordAccessExpr, RecordConstructExpr, RecordIndexExpr, RecordUpdateExpr};
 pub use self::records::{RecordAccessOrUpdateExpr, RecordConstructOrIndexExpr};
 pub use self::strings::StringExpr;
 pub use self::tuples::TupleExpr;
 
-pub type ParenthesizedExpr = Parenthesized<FullExpr>;
+/// `(` [Expr] `)`
+#[derive(Debug, Clone, Span, Parse)]
+pub struct ParenthesizedExpr(Parenthesized<FullExpr>);
+
+impl ParenthesizedExpr {
+    pub fn get(&self) -> &FullExpr {
+        self.0.get()
+    }
+
+    // The parentheses are redundant if this expression is a whole item of a list, tuple, argument list or clause body.
+    fn is_redundant(&self, fmt: &Formatter) -> bool {
+        let ts = fmt.token_stream();
+        if ts.contains_comment(self) {
+            return false;
+        }
+
+        let tokens = ts.visited_tokens();
+        let (Ok(i), Ok(j)) = (
+            tokens.binary_search_by_key(&self.start_position(), |t| t.start_position()),
+            tokens.binary_search_by_key(&self.end_position(), |t| t.end_position()),
+        ) else {
+            return false;
+        };
+        let (Some(prev), Some(next)) = (i.checked_sub(1).map(|i| &tokens[i]), tokens.get(j + 1))
+        else {
+            return false;
+        };
+        if prev.end_position() > self.start_position()
+            || next.start_position() < self.end_position()
+        {
+            // Tokens expanded from a macro.
+            return false;
+        }
+
+        let is_open = matches!(
+            prev,
+            LexicalToken::Symbol(x) if matches!(
+                x.value(),
+                Symbol::OpenParen
+                    | Symbol::OpenSquare
+                    | Symbol::OpenBrace
+                    | Symbol::Comma
+                    | Symbol::RightArrow
+            )
+        );
+        let is_close = match next {
+            LexicalToken::Symbol(x) => matches!(
+                x.value(),
+                Symbol::CloseParen
+                    | Symbol::CloseSquare
+                    | Symbol::CloseBrace
+                    | Symbol::Comma
+                    | Symbol::Semicolon
+                    | Symbol::Dot
+            ),
+            LexicalToken::Keyword(x) => x.value() == Keyword::End,
+            _ => false,
+        };
+        is_open && is_close
+    }
+}
+
+impl Format for ParenthesizedExpr {
+    fn format(&self, fmt: &mut Formatter) {
+        if fmt.options().remove_redundant_parens
+            && fmt.is_paren_removal_allowed()
+            && self.is_redundant(fmt)
+        {
+            self.get().format(fmt);
+        } else {
+            self.0.format(fmt);
+        }
+    }
+}
 
 #[derive(Debug, Clone, Span, Format)]
 pub enum BaseExpr {
@@ -52,6 +127,7 @@ pub enum BaseExpr {
     Parenthesized(Box<ParenthesizedExpr>),
     Literal(LiteralExpr),
     Block(Box<BlockExpr>),
+    OpaqueTerm(Box<OpaqueTermExpr>),
 
     // Left recursive.
     MapUpdate(Box<MapUpdateExpr>),
@@ -88,9 +164,16 @@ impl Parse for BaseExpr {
                 Symbol::OpenBrace => ts.parse().map(Self::Tuple),
                 Symbol::DoubleLeftAngle => ts.parse().map(Self::Bitstring),
                 Symbol::OpenParen => ts.parse().map(Self::Parenthesized),
+                Symbol::Less if ts.is_term_mode() => ts.parse().map(Self::OpaqueTerm),
                 Symbol::Sharp => {
                     if ts.peek::<(LexicalToken, OpenBraceSymbol)>().is_some() {
                         ts.parse().map(Self::Map)
+                    } else if ts.is_term_mode()
+                        && ts
+                            .peek::<(LexicalToken, VariableToken, LessSymbol)>()
+                            .is_some()
+                    {
+                        ts.parse().map(Self::OpaqueTerm)
                     } else {
                         ts.parse().map(Self::RecordConstructOrIndex)
                     }
@@ -134,6 +217,7 @@ impl Element for BaseExpr {
         match self {
             Self::UnaryOpCall(x) => x.item().is_packable(),
             Self::Literal(x) => x.is_packable(),
+            Self::OpaqueTerm(_) => true,
             _ => false,
         }
     }
@@ -206,6 +290,7 @@ pub enum LiteralExpr {
 #[cfg(test)]
 mod tests {
     use crate::items::forms::Form;
+    use crate::items::Expr;
 
     #[test]
     fn when_works() {
@@ -223,6 +308,159 @@ mod tests {
         }
     }
 
+    #[test]
+    fn short_circuit_guard_works() {
+        // Like `,` and `;`, `andalso` and `orelse` only break where the original text breaks.
+        let texts = [
+            indoc::indoc! {"
+            foo(A, B, C, D) when A andalso B orelse C andalso D ->
+                ok."},
+            indoc::indoc! {"
+            foo(A, B, C, D)
+              when A andalso B orelse
+                   C andalso D ->
+                ok."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+
+        let text = indoc::indoc! {"
+            foo(A, B, C, D)
+              when A andalso B
+                orelse C andalso D ->
+                ok."};
+        let expected = indoc::indoc! {"
+            foo(A, B, C, D)
+              when A andalso B orelse
+                   C andalso D ->
+                ok."};
+        crate::assert_format!(text, expected, Form);
+    }
+
+    #[test]
+    fn short_circuit_expr_works() {
+        // Outside guards, `andalso` and `orelse` chains are laid out in the same way as in guards.
+        let texts = [
+            "Aaa andalso Bbb andalso Ccc",
+            indoc::indoc! {"
+            Aaa andalso Bbb andalso
+            Ccc orelse Ddd"},
+            indoc::indoc! {"
+            X = Aaa andalso
+                Bbb"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+            f() when Aaa andalso Bbb
+            andalso Ccc orelse Ddd -> Aaa andalso Bbb
+            andalso Ccc orelse Ddd."};
+        let expected = indoc::indoc! {"
+            f() when Aaa andalso Bbb andalso
+                     Ccc orelse Ddd ->
+                Aaa andalso Bbb andalso
+                Ccc orelse Ddd."};
+        crate::assert_format!(text, expected, Form);
+    }
+
+    #[test]
+    fn guard_separator_is_preserved() {
+        // `,`/`;` and `andalso`/`orelse` have different semantics (e.g., on exceptions),
+        // so they are never rewritten into each other.
+        let texts = [
+            ("foo(A,B)when A,B->ok.", "foo(A, B) when A, B -> ok."),
+            (
+                "foo(A,B)when A andalso B->ok.",
+                "foo(A, B) when A andalso B -> ok.",
+            ),
+            (
+                "foo(A,B,C)when A,B;C->ok.",
+                "foo(A, B, C) when A, B; C -> ok.",
+            ),
+            (
+                "foo(A,B,C)when A andalso B orelse C->ok.",
+                "foo(A, B, C) when A andalso B orelse C -> ok.",
+            ),
+            (
+                "foo(A,B,C)when A,B orelse C;not A->ok.",
+                "foo(A, B, C) when A, B orelse C; not A -> ok.",
+            ),
+        ];
+        for (text, expected) in texts {
+            crate::assert_format!(text, expected, Form);
+        }
+
+        let text = indoc::indoc! {"
+            foo(A, B, C)
+              when A,
+                   B;
+                   C ->
+                A andalso B orelse
+                C."};
+        crate::assert_format!(text, Form);
+    }
+
+    #[test]
+    fn type_test_bif_guard_works() {
+        // Type test BIFs such as `is_record/2,3` are formatted as ordinary calls.
+        let texts = [
+            indoc::indoc! {"
+            foo(X) when is_record(X, state), element(2, X) > 0 ->
+                ok."},
+            indoc::indoc! {"
+            foo(X)
+              when is_record(X, state),
+                   element(2, X) > 0;
+                   is_record(X, state, 3) ->
+                ok."},
+            indoc::indoc! {"
+            foo(X) when is_record(X,
+                                  state),
+                        is_tuple(X) ->
+                ok."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+
+        let text = indoc::indoc! {"
+            foo(X) when is_record( X,state ),element(2,X)>0
+            ; is_record(X,state,3)->
+                ok."};
+        let expected = indoc::indoc! {"
+            foo(X) when is_record(X, state),
+                        element(2, X) > 0;
+                        is_record(X, state, 3) ->
+                ok."};
+        crate::assert_format!(text, expected, Form);
+    }
+
+    #[test]
+    fn float_works() {
+        // Floats are written exactly as in the original text.
+        let texts = [
+            "1.0",
+            "0.5",
+            "-0.5",
+            "1.0e10",
+            "1.0e+10",
+            "1.0e-10",
+            "1.23E+4",
+            "1.23E-4",
+            "-1.5E3",
+            "1_000.000_1",
+            "[1.0e10, 1.23E+4, -0.5e-3]",
+        ];
+        for text in texts {
+            crate::assert_format!(text, super::Expr);
+        }
+
+        crate::assert_format!("{1.0E10,- 2.50e-3}", "{1.0E10, -2.50e-3}", super::Expr);
+    }
+
     #[test]
     fn space_char_works() {
         let texts = [indoc::indoc! {"
@@ -232,4 +470,89 @@ mod tests {
             crate::assert_format!(text, Form);
         }
     }
+
+    #[test]
+    fn remove_redundant_parens_works() {
+        let text = indoc::indoc! {"
+            -define(FOO, (1 + 2)).
+            -define(BAR(X), (X)).
+
+
+            foo(A, B) ->
+                X = (A + B),
+                Y = [(A + B), {(A), (B * 2)}, -(A)],
+                Z = (fun() -> A end)(),
+                W = foo((catch bar()), ?BAR((A + B))),
+                V = <<(A + B):8, (B)/binary>>,
+                (X + Y) * (Z + W),
+                (A  % comment
+                ),
+                (V).
+            "};
+        let expected = indoc::indoc! {"
+            -define(FOO, (1 + 2)).
+            -define(BAR(X), (X)).
+
+
+            foo(A, B) ->
+                X = (A + B),
+                Y = [A + B, {A, B * 2}, -(A)],
+                Z = (fun() -> A end)(),
+                W = foo(catch bar(), ?BAR((A + B))),
+                V = <<(A + B):8, (B)/binary>>,
+                (X + Y) * (Z + W),
+                (A  % comment
+                ),
+                V.
+            "};
+        let options = crate::format::FormatOptions::new().remove_redundant_parens(true);
+        crate::assert_format!(text, expected, crate::items::Module, options);
+
+        // Parentheses are kept by default.
+        crate::assert_format!(text, text, crate::items::Module);
+    }
+
+    #[test]
+    fn empty_collections_work() {
+        let expected = indoc::indoc! {"
+        foo() ->
+            foo(),
+            {},
+            [],
+            <<>>,
+            #{},
+            #foo{},
+            X#{}."};
+        let texts = [
+            indoc::indoc! {"
+            foo( ) ->
+                foo( ),
+                { },
+                [ ],
+                << >>,
+                #{ },
+                #foo{ },
+                X#{ }."},
+            indoc::indoc! {"
+            foo(
+            ) ->
+                foo(
+                ),
+                {
+                },
+                [
+                ],
+                <<
+                >>,
+                #{
+                },
+                #foo{
+                },
+                X#{
+                }."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, expected, Form);
+        }
+    }
 }
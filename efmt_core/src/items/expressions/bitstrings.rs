@@ -142,6 +142,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn bitstring_type_specifier_works() {
+        let texts = [
+            "<<X/binary, Y/bitstring, Z/bits, W/bytes>>",
+            "<<X:32/integer-unsigned-big, Y:64/float-little>>",
+            "<<X:4/unit:16, Y:2/binary-unit:8>>",
+            "<<X/utf8, Y/utf16-little, Z/utf32-big>>",
+            "<<$a/utf8, \"abc\"/utf16>>",
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        crate::assert_format!(
+            "<< X : 32 / integer - unsigned - big - unit : 8 , Y / utf8 >>",
+            "<<X:32/integer-unsigned-big-unit:8, Y/utf8>>",
+            Expr
+        );
+    }
+
+    #[test]
+    fn bitstring_delimiter_works() {
+        // No whitespace is written inside `<<` and `>>` of bitstring constructs (even if nested).
+        let texts = [
+            ("<< >>", "<<>>"),
+            ("<< 1, 2 >>", "<<1, 2>>"),
+            ("<< << \"x\" >>/binary >>", "<<<<\"x\">>/binary>>"),
+            (
+                "<< <<>>/binary, << >>/binary >>",
+                "<<<<>>/binary, <<>>/binary>>",
+            ),
+            ("<<<<<<1>>/binary>>/binary>>", "<<<<<<1>>/binary>>/binary>>"),
+        ];
+        for (text, expected) in texts {
+            crate::assert_format!(text, expected, Expr);
+        }
+    }
+
     #[test]
     fn bitstring_comprehension_works() {
         let texts = [
@@ -176,4 +214,30 @@ mod tests {
             crate::assert_format!(text, Expr);
         }
     }
+
+    #[test]
+    fn bitstring_comprehension_block_element_works() {
+        let texts = [
+            indoc::indoc! {"
+            << begin
+                   Y = X * 2,
+                   <<Y:8>>
+               end
+               || <<X:4>> <= B,
+                  X > 0 >>"},
+            indoc::indoc! {"
+            << fun(Y) ->
+                       <<Y:8>>
+               end
+               || <<X:4>> <= B >>"},
+            indoc::indoc! {"
+            << (fun(Y) ->
+                        <<Y:8>>
+                end)(X)
+               || <<X:4>> <= B >>"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+    }
 }
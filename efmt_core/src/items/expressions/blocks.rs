@@ -8,7 +8,7 @@ use crate::items::keywords::{
 use crate::items::symbols::{ColonSymbol, CommaSymbol, RightArrowSymbol, SemicolonSymbol};
 use crate::items::tokens::{AtomToken, VariableToken};
 use crate::items::Expr;
-use crate::parse::Parse;
+use crate::parse::{self, Parse};
 use crate::span::Span;
 use std::borrow::Cow;
 
@@ -24,7 +24,7 @@ pub enum BlockExpr {
 }
 
 impl BlockExpr {
-    pub fn children(&self) -> impl Iterator<Item = Cow<Expr>> {
+    pub fn children(&self) -> impl Iterator<Item = Cow<'_, Expr>> {
         match self {
             BlockExpr::Case(x) => {
                 Box::new(x.children().map(Cow::Borrowed)) as Box<dyn Iterator<Item = Cow<Expr>>>
@@ -49,7 +49,7 @@ pub struct CaseExpr {
     case: CaseKeyword,
     value: Expr,
     of: OfKeyword,
-    clauses: Clauses<CaseClause>,
+    clauses: CaseClauses,
     end: EndKeyword,
 }
 
@@ -91,7 +91,7 @@ impl Format for CaseExpr {
                 self.end.format(fmt);
             })
         };
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -99,6 +99,64 @@ impl Format for CaseExpr {
     }
 }
 
+#[derive(Debug, Clone, Span, Parse)]
+struct CaseClauses(Clauses<CaseClause>);
+
+impl CaseClauses {
+    fn iter(&self) -> impl Iterator<Item = &CaseClause> {
+        self.0.iter()
+    }
+
+    // Returns the column at which the `->` of single-line clauses are aligned
+    // if [FormatOptions::align_clause_arrows()](crate::format::FormatOptions::align_clause_arrows) is enabled.
+    fn arrow_column(&self, fmt: &mut Formatter) -> Option<usize> {
+        if !fmt.options().align_clause_arrows || !fmt.contains_newline(self) {
+            return None;
+        }
+
+        let start = fmt.column();
+        let max_column = fmt.options().max_columns.saturating_sub(" -> ".len());
+        let widths = self
+            .iter()
+            .filter_map(|clause| {
+                if fmt.contains_newline(clause) {
+                    return None;
+                }
+                fmt.single_line_width(clause.start_position(), |fmt| clause.format_head(fmt))
+            })
+            .filter(|width| start + width <= max_column)
+            .collect::<Vec<_>>();
+        if widths.len() < 2 {
+            return None;
+        }
+        widths.into_iter().max().map(|width| start + width)
+    }
+}
+
+impl Format for CaseClauses {
+    fn format(&self, fmt: &mut Formatter) {
+        let Some(arrow_column) = self.arrow_column(fmt) else {
+            self.0.format(fmt);
+            return;
+        };
+
+        fmt.with_scoped_indent(|fmt| {
+            fmt.set_indent(fmt.column());
+
+            let mut delimiters = self.0.delimiters().iter();
+            for (i, clause) in self.iter().enumerate() {
+                if i > 0 {
+                    if let Some(delimiter) = delimiters.next() {
+                        delimiter.format(fmt);
+                    }
+                    fmt.write_newline();
+                }
+                clause.format_with_arrow_column(fmt, Some(arrow_column));
+            }
+        });
+    }
+}
+
 #[derive(Debug, Clone, Span, Parse)]
 struct CaseClause {
     pattern: Expr,
@@ -113,30 +171,40 @@ impl CaseClause {
             .chain(self.guard.get().into_iter().flat_map(|x| x.children()))
             .chain(self.body.exprs())
     }
-}
 
-impl Format for CaseClause {
-    fn format(&self, fmt: &mut Formatter) {
-        fmt.with_scoped_indent(|fmt| {
-            let base_ident = fmt.indent();
+    fn format_head(&self, fmt: &mut Formatter) {
+        let base_ident = fmt.indent();
 
-            // 'Pattern'
-            self.pattern.format(fmt);
+        // 'Pattern'
+        self.pattern.format(fmt);
 
-            // 'when'
-            if let Some(guard) = self.guard.get() {
-                if fmt.has_newline_until(guard) {
-                    fmt.set_indent(base_ident + 2);
-                    fmt.write_newline();
-                } else {
-                    fmt.write_space();
-                }
-                guard.format(fmt);
+        // 'when'
+        if let Some(guard) = self.guard.get() {
+            if fmt.has_newline_until(guard) {
+                fmt.set_indent(base_ident + 2);
+                fmt.write_newline();
+            } else {
+                fmt.write_space();
             }
+            guard.format(fmt);
+        }
+    }
+
+    fn format_with_arrow_column(&self, fmt: &mut Formatter, arrow_column: Option<usize>) {
+        fmt.with_scoped_indent(|fmt| {
+            let base_ident = fmt.indent();
+
+            // 'Pattern' 'when'
+            self.format_head(fmt);
 
             // '->'
             let multiline = fmt.has_newline_until(&self.body.end_position());
-            fmt.write_space();
+            match arrow_column {
+                Some(column) if !multiline && fmt.column() < column => {
+                    fmt.write_spaces(column + 1 - fmt.column());
+                }
+                _ => fmt.write_space(),
+            }
             self.arrow.format(fmt);
 
             // 'Body'
@@ -151,6 +219,12 @@ impl Format for CaseClause {
     }
 }
 
+impl Format for CaseClause {
+    fn format(&self, fmt: &mut Formatter) {
+        self.format_with_arrow_column(fmt, None);
+    }
+}
+
 /// `if` (`$CLAUSE` `;`?)+ `end`
 ///
 /// - $CLAUSE: `$GUARD` `->` [Body]
@@ -189,7 +263,7 @@ impl Format for IfExpr {
                 self.end.format(fmt);
             })
         };
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -273,7 +347,7 @@ impl Format for BeginExpr {
                 self.end.format(fmt);
             })
         };
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -290,7 +364,7 @@ impl Format for BeginExpr {
 #[derive(Debug, Clone, Span, Parse)]
 pub struct ReceiveExpr {
     receive: ReceiveKeyword,
-    clauses: Maybe<Clauses<CaseClause>>,
+    clauses: Maybe<CaseClauses>,
     timeout: Maybe<ReceiveTimeout>,
     end: EndKeyword,
 }
@@ -334,7 +408,10 @@ impl Format for ReceiveExpr {
                 self.end.format(fmt);
             })
         };
-        if self.contains_newline() {
+        let compact = fmt.options().compact_receive
+            && self.clauses.get().is_none()
+            && fmt.fits_in_single_line(|fmt| fmt.with_single_line_mode(f));
+        if fmt.contains_newline(self) && !compact {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -392,14 +469,14 @@ impl Format for ReceiveTimeout {
 pub struct TryExpr {
     r#try: TryKeyword,
     body: Body,
-    clauses: Maybe<(OfKeyword, Clauses<CaseClause>)>,
+    clauses: Maybe<(OfKeyword, CaseClauses)>,
     catch: Maybe<TryCatch>,
     after: Maybe<TryAfter>,
     end: EndKeyword,
 }
 
 impl TryExpr {
-    fn children(&self) -> impl Iterator<Item = Cow<Expr>> {
+    fn children(&self) -> impl Iterator<Item = Cow<'_, Expr>> {
         self.body
             .exprs()
             .iter()
@@ -492,7 +569,10 @@ impl Format for TryExpr {
                 self.end.format(fmt);
             })
         };
-        if self.contains_newline() {
+        let compact = fmt.options().compact_try
+            && self.is_compactable()
+            && fmt.fits_in_single_line(|fmt| fmt.with_single_line_mode(f));
+        if fmt.contains_newline(self) && !compact {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -500,6 +580,18 @@ impl Format for TryExpr {
     }
 }
 
+impl TryExpr {
+    fn is_compactable(&self) -> bool {
+        self.clauses
+            .get()
+            .is_some_and(|(_, clauses)| clauses.iter().count() == 1)
+            && self
+                .catch
+                .get()
+                .is_none_or(|catch| catch.clauses.iter().count() == 1)
+    }
+}
+
 #[derive(Debug, Clone, Span, Parse)]
 struct TryCatch {
     catch: CatchKeyword,
@@ -507,7 +599,7 @@ struct TryCatch {
 }
 
 impl TryCatch {
-    fn children(&self) -> impl Iterator<Item = Cow<Expr>> {
+    fn children(&self) -> impl Iterator<Item = Cow<'_, Expr>> {
         self.clauses.iter().flat_map(|x| x.children())
     }
 }
@@ -521,7 +613,7 @@ struct CatchClause {
 }
 
 impl CatchClause {
-    fn children(&self) -> impl Iterator<Item = Cow<Expr>> {
+    fn children(&self) -> impl Iterator<Item = Cow<'_, Expr>> {
         self.pattern.children().chain(
             self.guard
                 .get()
@@ -578,7 +670,7 @@ struct CatchPattern {
 }
 
 impl CatchPattern {
-    fn children(&self) -> impl Iterator<Item = Cow<Expr>> {
+    fn children(&self) -> impl Iterator<Item = Cow<'_, Expr>> {
         self.class
             .get()
             .and_then(|x| {
@@ -643,7 +735,7 @@ impl Format for CatchExpr {
 /// - $CLAUSE: `$PATTERN` (`when` `$GUARD`)? `->` [Body]
 /// - $PATTERN: [Expr]
 /// - $GUARD: ([Expr] (`,` | `;`)?)+
-#[derive(Debug, Clone, Span, Parse)]
+#[derive(Debug, Clone, Span)]
 pub struct MaybeExpr {
     maybe: MaybeKeyword,
     body: Body,
@@ -651,6 +743,19 @@ pub struct MaybeExpr {
     end: EndKeyword,
 }
 
+impl Parse for MaybeExpr {
+    fn parse(ts: &mut parse::TokenStream) -> parse::Result<Self> {
+        let expr = Self {
+            maybe: ts.parse()?,
+            body: ts.parse()?,
+            else_block: ts.parse()?,
+            end: ts.parse()?,
+        };
+        ts.require_otp(25, "maybe expressions", &expr);
+        Ok(expr)
+    }
+}
+
 impl MaybeExpr {
     fn children(&self) -> impl Iterator<Item = &Expr> {
         self.body
@@ -693,7 +798,7 @@ impl Format for MaybeExpr {
                 self.end.format(fmt);
             })
         };
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -704,7 +809,7 @@ impl Format for MaybeExpr {
 #[derive(Debug, Clone, Span, Parse)]
 struct ElseBlock {
     else_keyword: ElseKeyword,
-    clauses: Clauses<CaseClause>,
+    clauses: CaseClauses,
 }
 
 impl ElseBlock {
@@ -741,6 +846,115 @@ mod tests {
         }
     }
 
+    #[test]
+    fn comment_between_case_clauses_works() {
+        let text = indoc::indoc! {"
+            case X of
+                1 -> a;
+                % note
+                {ok, Y} -> b;
+              % misaligned note
+                _ -> c  % trailing
+            end"};
+        let expected = indoc::indoc! {"
+            case X of
+                1 -> a;
+                % note
+                {ok, Y} -> b;
+                % misaligned note
+                _ -> c  % trailing
+            end"};
+        crate::assert_format!(text, expected, Expr);
+
+        let expected = indoc::indoc! {"
+            case X of
+                1       -> a;
+                % note
+                {ok, Y} -> b;
+                % misaligned note
+                _       -> c  % trailing
+            end"};
+        let options = crate::format::FormatOptions::new().align_clause_arrows(true);
+        crate::assert_format!(text, expected, Expr, options);
+    }
+
+    #[test]
+    fn align_clause_arrows_works() {
+        let text = indoc::indoc! {"
+            case X of
+                0 -> zero;
+                {ok, Value} when Value > 0 -> positive;
+                [_ | _] -> list;
+                Other ->
+                    {error, Other}
+            end"};
+        let expected = indoc::indoc! {"
+            case X of
+                0                          -> zero;
+                {ok, Value} when Value > 0 -> positive;
+                [_ | _]                    -> list;
+                Other ->
+                    {error, Other}
+            end"};
+        let options = crate::format::FormatOptions::new().align_clause_arrows(true);
+        crate::assert_format!(text, expected, Expr, options);
+
+        // A clause that would exceed `max_columns` once aligned is formatted as usual.
+        let expected = indoc::indoc! {"
+            case X of
+                0       -> zero;
+                {ok, Value} when Value > 0 -> positive;
+                [_ | _] -> list;
+                Other ->
+                    {error, Other}
+            end"};
+        let options = options.max_columns(30);
+        crate::assert_format!(text, expected, Expr, options);
+
+        // Arrows are not aligned by default.
+        crate::assert_format!(text, text, Expr);
+    }
+
+    #[test]
+    fn dispatch_table_works() {
+        // Single-line clauses stay in a single line, so a large switch is kept as a table.
+        let table = indoc::indoc! {"
+            case Atom of
+                one -> 1;
+                two -> 2;
+                three -> 3;
+                four -> 4;
+                five -> 5;
+                six -> 6;
+                seven -> 7;
+                eight -> 8;
+                nine -> 9;
+                ten -> 10
+            end"};
+        crate::assert_format!(table, Expr);
+
+        let text = table.replace(" -> ", "->").replace(';', " ;");
+        crate::assert_format!(text, table, Expr);
+
+        let aligned = indoc::indoc! {"
+            case Atom of
+                one   -> 1;
+                two   -> 2;
+                three -> 3;
+                four  -> 4;
+                five  -> 5;
+                six   -> 6;
+                seven -> 7;
+                eight -> 8;
+                nine  -> 9;
+                ten   -> 10
+            end"};
+        let options = crate::format::FormatOptions::new().align_clause_arrows(true);
+        crate::assert_format!(table, aligned, Expr, options);
+        crate::assert_format!(aligned, aligned, Expr, options);
+        assert_eq!(aligned.lines().count(), 12);
+    }
+
     #[test]
     fn if_works() {
         let texts = [
@@ -804,6 +1018,88 @@ mod tests {
         }
     }
 
+    #[test]
+    fn compact_receive_works() {
+        let options = crate::format::FormatOptions::new().compact_receive(true);
+        let text = indoc::indoc! {"
+        receive
+        after
+            1000 ->
+                ok
+        end"};
+        crate::assert_format!(text, "receive after 1000 -> ok end", Expr, options);
+
+        // Too long to fit in a single line.
+        let expected = indoc::indoc! {"
+        receive
+        after
+            1000 ->
+                ok
+        end"};
+        crate::assert_format!(text, expected, Expr, options.clone().max_columns(20));
+
+        // Receives that have clauses are not affected.
+        let text = indoc::indoc! {"
+        receive
+            A ->
+                A
+        after
+            1000 ->
+                ok
+        end"};
+        crate::assert_format!(text, text, Expr, options);
+
+        // Comments prevent compaction.
+        let text = indoc::indoc! {"
+        receive
+        after
+            1000 ->
+                %% wait
+                ok
+        end"};
+        crate::assert_format!(text, text, Expr, options);
+    }
+
+    #[test]
+    fn compact_try_works() {
+        let options = crate::format::FormatOptions::new().compact_try(true);
+        let text = indoc::indoc! {"
+        try X of
+            ok ->
+                done
+        catch
+            _ ->
+                err
+        end"};
+        let compact = "try X of ok -> done catch _ -> err end";
+        crate::assert_format!(text, compact, Expr, options);
+        crate::assert_format!(compact, compact, Expr, options);
+
+        // Expanded unless enabled.
+        crate::assert_format!(text, Expr);
+
+        // Too long to fit in a single line.
+        crate::assert_format!(text, text, Expr, options.clone().max_columns(30));
+
+        // Multiple clauses prevent compaction.
+        let text = indoc::indoc! {"
+        try X of
+            ok -> done;
+            error -> retry
+        catch
+            _ -> err
+        end"};
+        crate::assert_format!(text, text, Expr, options);
+        let text = indoc::indoc! {"
+        try X of
+            ok -> done
+        catch
+            throw:_ -> err;
+            _ -> err
+        end"};
+        crate::assert_format!(text, text, Expr, options);
+    }
+
     #[test]
     fn begin_works() {
         let texts = [
@@ -906,6 +1202,131 @@ mod tests {
         }
     }
 
+    #[test]
+    fn try_with_all_sections_works() {
+        // Each section is indented under its keyword, and the keywords are aligned with `try`.
+        let texts = [
+            indoc::indoc! {"
+            try
+                X = bar(aaaa,
+                        bbbb),
+                X
+            of
+                {ok, Y} ->
+                    baz(Y,
+                        qux);
+                error ->
+                    error
+            catch
+                error:Reason:Stack ->
+                    log(Reason,
+                        Stack)
+            after
+                cleanup(aaaa,
+                        bbbb),
+                ok
+            end"},
+            indoc::indoc! {"
+            try bar(aaaa,
+                    bbbb) of
+                {ok, Y} ->
+                    baz(Y,
+                        qux)
+            catch
+                error:Reason:Stack ->
+                    log(Reason,
+                        Stack)
+            after
+                cleanup(aaaa,
+                        bbbb)
+            end"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+        try
+        X=bar(aaaa,
+        bbbb),
+        X
+        of
+        {ok,Y}->
+        baz(Y,
+        qux);
+        error->
+        error
+        catch
+        error:Reason:Stack->
+        log(Reason,
+        Stack)
+        after
+        cleanup(aaaa,
+        bbbb),
+        ok
+        end"};
+        crate::assert_format!(text, texts[0], Expr);
+    }
+
+    #[test]
+    fn catch_clause_guard_works() {
+        // Guards in catch clauses are laid out in the same way as those in case clauses.
+        let texts = [
+            indoc::indoc! {"
+            try
+                foo()
+            catch
+                error:#{reason := R} when is_map(R), map_size(R) > 0 ->
+                    R
+            end"},
+            indoc::indoc! {"
+            try
+                foo()
+            catch
+                error:#{reason := R}:Stacktrace when is_map(R),
+                                                     map_size(R) > 0 ->
+                    {R, Stacktrace}
+            end"},
+            indoc::indoc! {"
+            try
+                foo()
+            catch
+                error:#{reason := R}
+                  when is_map(R),
+                       map_size(R) > 0 ->
+                    R
+            end"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+            try foo()
+            catch error:#{reason:=R}
+                      when is_map(R),
+                    map_size(R)>0->
+            R end"};
+        let expected = indoc::indoc! {"
+            try
+                foo()
+            catch
+                error:#{reason := R}
+                  when is_map(R),
+                       map_size(R) > 0 ->
+                    R
+            end"};
+        crate::assert_format!(text, expected, Expr);
+        let text = indoc::indoc! {"
+            case foo() of
+                #{reason := R}
+                  when is_map(R),
+                       map_size(R) > 0 ->
+                    R
+            end"};
+        crate::assert_format!(text, Expr);
+    }
+
     #[test]
     fn catch_works() {
         let texts = [
@@ -920,6 +1341,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn catch_in_try_body_works() {
+        // A `catch` right after `try` is a prefix `catch` expression because the body of `try` cannot be empty.
+        let texts = [
+            indoc::indoc! {"
+            try
+                catch foo()
+            after
+                ok
+            end"},
+            indoc::indoc! {"
+            try
+                catch foo()
+            catch
+                _:_ ->
+                    error
+            end"},
+            indoc::indoc! {"
+            try
+                X = catch foo(),
+                catch bar(X)
+            of
+                {'EXIT', _} ->
+                    error;
+                _ ->
+                    ok
+            catch
+                throw:Y ->
+                    catch baz(Y)
+            end"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        crate::assert_format!("try catch foo() after ok end", Expr);
+        crate::assert_format!(
+            "try  catch  foo( )  after  ok  end",
+            "try catch foo() after ok end",
+            Expr
+        );
+    }
+
     #[test]
     fn maybe_works() {
         let texts = [
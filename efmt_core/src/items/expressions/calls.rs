@@ -1,4 +1,4 @@
-use crate::format::{Format, Formatter};
+use crate::format::{Format, Formatter, OperatorBreak};
 use crate::items::components::{Args, Maybe};
 use crate::items::expressions::components::{BinaryOp, UnaryOp};
 use crate::items::expressions::BaseExpr;
@@ -130,23 +130,36 @@ impl BinaryOpCallExpr {
 
     fn format_binary_op(&self, fmt: &mut Formatter, mut update_indent: bool) {
         self.left.format(fmt);
-        fmt.write_space();
 
-        update_indent |= matches!(
+        let is_match_or_send = matches!(
             self.op,
             BinaryOp::Match(_) | BinaryOp::MaybeMatch(_) | BinaryOp::Send(_)
         );
+        update_indent |= is_match_or_send;
         let multiline = fmt.has_newline_until(&self.right);
+        let break_before = multiline
+            && !is_match_or_send
+            && fmt.options().operator_break == OperatorBreak::Before
+            && !fmt
+                .token_stream()
+                .contains_comment(&(self.left.end_position(), self.right.start_position()));
 
-        self.op.format(fmt);
-        if multiline {
-            if update_indent {
-                fmt.set_indent(fmt.indent() + 4);
-                update_indent = false;
-            }
+        if break_before {
             fmt.write_newline();
+            self.op.format(fmt);
+            fmt.write_space();
         } else {
             fmt.write_space();
+            self.op.format(fmt);
+            if multiline {
+                if update_indent {
+                    fmt.set_indent(fmt.indent() + 4);
+                    update_indent = false;
+                }
+                fmt.write_newline();
+            } else {
+                fmt.write_space();
+            }
         }
 
         if let Some(right) = self.right.as_binary_op() {
@@ -175,6 +188,7 @@ impl Format for BinaryOpCallExpr {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::format::{FormatOptions, OperatorBreak};
 
     #[test]
     fn function_call_works() {
@@ -209,12 +223,90 @@ mod tests {
         }
     }
 
+    #[test]
+    fn nested_call_works() {
+        // Nested calls are only broken where the original text breaks.
+        let texts = [
+            "alpha(beta(gamma(delta(Xvalue))))",
+            indoc::indoc! {"
+            alpha(beta(gamma(delta(X))),
+                  Y)"},
+            indoc::indoc! {"
+            alpha(beta(gamma(delta(X,
+                                   Y))))"},
+            indoc::indoc! {"
+            maps:get(a,
+                     maps:get(b,
+                              maps:get(c, M)))"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+            alpha( beta(gamma(delta(X))) ,
+            Y)"};
+        crate::assert_format!(text, texts[1], Expr);
+    }
+
+    #[test]
+    fn quoted_remote_call_works() {
+        let texts = [
+            "'weird-mod':'do-it'(X)",
+            "'weird-mod':'do-it'(X, 'a$b')",
+            indoc::indoc! {"
+            'weird-mod':'do-it'(X,
+                                Y,
+                                Z)"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        crate::assert_format!(
+            "'weird-mod' : 'do-it'( X,'a$b' )",
+            "'weird-mod':'do-it'(X, 'a$b')",
+            Expr
+        );
+    }
+
+    #[test]
+    fn quoted_operator_call_works() {
+        let texts = [
+            "erlang:'=='(A, B)",
+            "erlang:'+'(1, 2)",
+            "'andalso'(A, B)",
+            "erlang:'=:='(A,\n             B)",
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        crate::assert_format!("erlang : '==' ( A,B )", "erlang:'=='(A, B)", Expr);
+    }
+
     #[test]
     fn unary_op_call_works() {
         let texts = ["-1", "bnot Foo(1, +2, 3)", "- -7", "+ + -3", "foo(-1)"];
         for text in texts {
             crate::assert_format!(text, Expr);
         }
+
+        // Symbolic operators are written without a space, and word operators with a space.
+        let texts = [
+            ("- X", "-X"),
+            ("+ X", "+X"),
+            ("-1.5", "-1.5"),
+            ("- - X", "- -X"),
+            ("-( A+B )", "-(A + B)"),
+            ("not   is_atom(X)", "not is_atom(X)"),
+            ("not not X", "not not X"),
+            ("bnot  X", "bnot X"),
+            ("X- -Y", "X - -Y"),
+        ];
+        for (text, expected) in texts {
+            crate::assert_format!(text, expected, Expr);
+        }
     }
 
     #[test]
@@ -250,4 +342,225 @@ mod tests {
             crate::assert_format!(text, Expr);
         }
     }
+
+    #[test]
+    fn comparison_op_call_works() {
+        for op in ["==", "/=", "=<", ">=", "<", ">", "=:=", "=/="] {
+            let expected = format!("A {op} B");
+            crate::assert_format!(format!("A{op}B"), expected, Expr);
+            crate::assert_format!(expected, Expr);
+        }
+
+        let texts = [indoc::indoc! {"
+            foo(A, B) when A =:= B;
+                           A =/= undefined andalso
+                           B =/= undefined ->
+                ok.
+            "}];
+        for text in texts {
+            crate::assert_format!(text, crate::items::Module);
+        }
+
+        let text = indoc::indoc! {"
+        foo(A, B) when A=:=B; A=/=undefined andalso
+        B =/= undefined ->
+            ok.
+        "};
+        crate::assert_format!(text, texts[0], crate::items::Module);
+    }
+
+    #[test]
+    fn operator_break_works() {
+        let after = indoc::indoc! {"
+        A andalso
+        B andalso
+        C"};
+        let before = indoc::indoc! {"
+        A
+        andalso B
+        andalso C"};
+        let options = FormatOptions::new().operator_break(OperatorBreak::Before);
+        for text in [after, before] {
+            crate::assert_format!(text, after, Expr);
+            crate::assert_format!(text, before, Expr, options);
+        }
+
+        let texts = [
+            indoc::indoc! {"
+            [1, 2, 3]
+            ++ [4, 5, 6]
+            ++ [7]"},
+            indoc::indoc! {"
+            foo(A + B
+                - C)"},
+            indoc::indoc! {"
+            X =
+                A
+                orelse B"},
+            indoc::indoc! {"
+            X --  % comment
+            Y"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, text, Expr, options);
+        }
+    }
+
+    #[test]
+    fn mixed_precedence_op_call_works() {
+        // Line breaks are only placed where the original text has them.
+        let texts = [
+            "Aaaa + Bbbb * Cccc + Dddd",
+            indoc::indoc! {"
+            Aaaa +
+            Bbbb * Cccc +
+            Dddd"},
+            indoc::indoc! {"
+            Aaaa bor Bbbb band Cccc bxor
+            Dddd -- Eeee"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+            Aaaa+
+                Bbbb*Cccc
+                  +Dddd"};
+        let expected = indoc::indoc! {"
+            Aaaa +
+            Bbbb * Cccc +
+            Dddd"};
+        crate::assert_format!(text, expected, Expr);
+    }
+
+    #[test]
+    fn send_op_call_works() {
+        // Line breaks are only placed where the original text has them.
+        let texts = [
+            "self() ! {long, message, tuple, here}",
+            indoc::indoc! {"
+            self() !
+                {long, message, tuple, here}"},
+            indoc::indoc! {"
+            self() !
+                {long,
+                 message,
+                 tuple,
+                 here}"},
+            indoc::indoc! {"
+            A ! B ! C =
+                foo()"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+            self()!
+              {long, message,
+            tuple, here}"};
+        let expected = indoc::indoc! {"
+            self() !
+                {long, message,
+                       tuple, here}"};
+        crate::assert_format!(text, expected, Expr);
+    }
+
+    #[test]
+    fn match_op_call_works() {
+        // `=` has the lowest precedence, so the whole right-hand side is placed under a continuation indent.
+        let texts = [
+            "Long = a + b + c + d",
+            indoc::indoc! {"
+            Long = a + b +
+                c + d"},
+            indoc::indoc! {"
+            Long =
+                a + b + c + d"},
+            indoc::indoc! {"
+            Long = Short = a +
+                b"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+            Long
+            = a + b
+            + c + d"};
+        let expected = indoc::indoc! {"
+            Long =
+                a + b +
+                c + d"};
+        crate::assert_format!(text, expected, Expr);
+
+        // `=` is never moved to the next line even if `operator_break` is `before`.
+        let options = FormatOptions::new().operator_break(OperatorBreak::Before);
+        let expected = indoc::indoc! {"
+            Long =
+                a + b
+                + c + d"};
+        crate::assert_format!(text, expected, Expr, options);
+    }
+
+    #[test]
+    fn list_op_call_works() {
+        let texts = [
+            "A -- B -- C",
+            "A ++ B ++ C",
+            "X -- -Y",
+            "X -- -1",
+            indoc::indoc! {"
+            [1, 2, 3] ++
+            [4, 5, 6] ++ [7]"},
+            indoc::indoc! {"
+            X --  % comment
+            Y"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        crate::assert_format!("X--Y", "X -- Y", Expr);
+        crate::assert_format!("X-- -Y", "X -- -Y", Expr);
+        crate::assert_format!("[1,2,3]++[4,5,6]", "[1, 2, 3] ++ [4, 5, 6]", Expr);
+    }
+
+    #[test]
+    fn guard_and_body_call_works() {
+        // Calls (including guard BIFs) are formatted the same in guards and in bodies.
+        let texts = [
+            ("tuple_size(X)=:=2", "tuple_size(X) =:= 2"),
+            ("erlang:tuple_size( X )=:=2", "erlang:tuple_size(X) =:= 2"),
+            (
+                "is_map_key(a,X) andalso map_get(a,X)>0",
+                "is_map_key(a, X) andalso map_get(a, X) > 0",
+            ),
+            ("not is_atom(X)", "not is_atom(X)"),
+        ];
+        for (text, expected) in texts {
+            crate::assert_format!(text, expected, Expr);
+            crate::assert_format!(
+                format!("foo(X) when {text} -> {text}."),
+                format!("foo(X) when {expected} -> {expected}.").as_str(),
+                crate::items::forms::Form
+            );
+        }
+
+        let text = indoc::indoc! {"
+        foo(X) when element(1,
+        X)==ok, tuple_size(X)=:=2 ->
+            {element(1,
+        X)==ok, tuple_size(X)=:=2}."};
+        let expected = indoc::indoc! {"
+        foo(X) when element(1,
+                            X) == ok,
+                    tuple_size(X) =:= 2 ->
+            {element(1,
+                     X) == ok,
+             tuple_size(X) =:= 2}."};
+        crate::assert_format!(text, expected, crate::items::forms::Form);
+    }
 }
@@ -92,7 +92,7 @@ impl<Name: Format, const BODY_INDENT: usize> Format for FunctionClause<Name, BOD
             });
         };
 
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
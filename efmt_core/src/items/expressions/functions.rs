@@ -50,7 +50,7 @@ impl FunctionExpr {
         Some(&x.arity)
     }
 
-    pub fn children(&self) -> impl Iterator<Item = Cow<Expr>> {
+    pub fn children(&self) -> impl Iterator<Item = Cow<'_, Expr>> {
         match self {
             FunctionExpr::Defined(x) => Box::new(
                 x.module
@@ -132,7 +132,7 @@ impl Format for AnonymousFunctionExpr {
                 self.end.format(fmt);
             })
         };
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -172,7 +172,7 @@ impl Format for NamedFunctionExpr {
                 self.end.format(fmt);
             })
         };
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -186,7 +186,13 @@ mod tests {
 
     #[test]
     fn defined_function_works() {
-        let texts = ["fun foo/1", "fun foo:bar/Arity", "fun (foo()):Bar/(baz())"];
+        let texts = [
+            "fun foo/1",
+            "fun foo:bar/Arity",
+            "fun (foo()):Bar/(baz())",
+            "fun erlang:'+'/2",
+            "fun erlang:'andalso'/2",
+        ];
         for text in texts {
             crate::assert_format!(text, Expr);
         }
@@ -232,6 +238,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn anonymous_function_edge_cases_work() {
+        let texts = [
+            "fun() -> [] end",
+            "fun(_) -> ok end",
+            "fun() -> case X of a -> 1 end end",
+            indoc::indoc! {"
+            fun() ->
+                    case X of
+                        a ->
+                            1;
+                        _ ->
+                            2
+                    end
+            end"},
+            indoc::indoc! {"
+            fun(X) ->
+                    fun() ->
+                            case X of
+                                a -> 1
+                            end
+                    end
+            end"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+            fun () ->
+              case X of
+                a -> 1
+              end
+            end"};
+        let expected = indoc::indoc! {"
+            fun() ->
+                    case X of
+                        a -> 1
+                    end
+            end"};
+        crate::assert_format!(text, expected, Expr);
+    }
+
     #[test]
     fn named_function_works() {
         let texts = [
@@ -51,8 +51,17 @@ pub struct ImproperListConstructExpr {
 impl Format for ImproperListConstructExpr {
     fn format(&self, fmt: &mut Formatter) {
         self.open.format(fmt);
+        let indent = fmt.column();
         self.items.format(fmt);
-        fmt.write_space();
+        if fmt.has_newline_until(&self.bar) {
+            // The tail is aligned with the elements.
+            fmt.with_scoped_indent(|fmt| {
+                fmt.set_indent(indent);
+                fmt.write_newline();
+            });
+        } else {
+            fmt.write_space();
+        }
         self.bar.format(fmt);
         fmt.write_space();
         self.last.format(fmt);
@@ -97,6 +106,86 @@ mod tests {
         }
     }
 
+    #[test]
+    fn collection_layout_works() {
+        use crate::format::{CollectionLayout, FormatOptions};
+
+        let aligned = indoc::indoc! {"
+            foo() ->
+                X = [foo,
+                     {bar, [1, 2, 3]},
+                     <<1, 2>>],
+                [{a,
+                  b},
+                 c]."};
+        let hanging = indoc::indoc! {"
+            foo() ->
+                X = [
+                    foo,
+                    {bar, [1, 2, 3]},
+                    <<1, 2>>
+                ],
+                [
+                    {
+                        a,
+                        b
+                    },
+                    c
+                ]."};
+        let options = FormatOptions::new().collection_layout(CollectionLayout::Hanging);
+        for text in [aligned, hanging] {
+            crate::assert_format!(text, hanging, crate::items::forms::Form, options);
+        }
+        crate::assert_format!(
+            aligned,
+            aligned,
+            crate::items::forms::Form,
+            FormatOptions::new()
+        );
+
+        // Single-line collections are not affected.
+        let text = "[foo, {bar, baz}, <<1, 2>>]";
+        crate::assert_format!(text, text, Expr, options);
+    }
+
+    #[test]
+    fn pack_collections_works() {
+        use crate::format::FormatOptions;
+
+        let packed = indoc::indoc! {"
+            [1, 2, 3, 4,
+             5, 6, 7, 8,
+             9, 10]"};
+        let unpacked = indoc::indoc! {"
+            [1,
+             2,
+             3,
+             4,
+             5,
+             6,
+             7,
+             8,
+             9,
+             10]"};
+        crate::assert_format!(packed, Expr);
+        crate::assert_format!(unpacked, Expr);
+
+        let options = FormatOptions::new().pack_collections(false);
+        crate::assert_format!(packed, unpacked, Expr, options);
+        crate::assert_format!(unpacked, unpacked, Expr, options);
+
+        // Single-line collections, tuples and records are handled in the same way.
+        let text = "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]";
+        crate::assert_format!(text, text, Expr, options);
+        crate::assert_format!("{1, 2,\n 3}", "{1,\n 2,\n 3}", Expr, options);
+        crate::assert_format!(
+            "#foo{a = 1, b = 2,\n     c = 3}",
+            "#foo{\n  a = 1,\n  b = 2,\n  c = 3\n }",
+            Expr,
+            options
+        );
+    }
+
     #[test]
     fn improper_list_works() {
         let texts = [
@@ -109,6 +198,30 @@ mod tests {
         for text in texts {
             crate::assert_format!(text, Expr);
         }
+
+        // A line break before `|` is kept and the tail is aligned with the elements.
+        let texts = [
+            "[H | T]",
+            "[a, b | T]",
+            "[A | [B | [C | D]]]",
+            indoc::indoc! {"
+            [1, 2, 3,
+             4, 5 | Rest]"},
+            indoc::indoc! {"
+            [1, 2, 3, 4, 5
+             | Rest]"},
+            indoc::indoc! {"
+            [foo(),
+             bar()
+             | T]"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        crate::assert_format!("[H|T]", "[H | T]", Expr);
+        crate::assert_format!("[1,2,3,4,5\n|Rest]", "[1, 2, 3, 4, 5\n | Rest]", Expr);
+        crate::assert_format!("[1,2,3,4,5|\nRest]", "[1, 2, 3, 4, 5 | Rest]", Expr);
     }
 
     #[test]
@@ -133,4 +246,89 @@ mod tests {
             crate::assert_format!(text, Expr);
         }
     }
+
+    #[test]
+    fn block_filter_in_comprehension_works() {
+        let texts = [
+            indoc::indoc! {"
+            [ X || X <- L,
+                   case X of
+                       1 -> true;
+                       _ -> false
+                   end ]"},
+            indoc::indoc! {"
+            [ X
+              || X <- L,
+                 begin
+                     Y = X * 2,
+                     Y > 3
+                 end ]"},
+            indoc::indoc! {"
+            #{ K => V || K := V <- M,
+                         case V of
+                             1 -> true;
+                             _ -> false
+                         end }"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+        [X || X <- L, case X of
+        1 -> true;
+        _ -> false end]"};
+        crate::assert_format!(text, texts[0], Expr);
+    }
+
+    #[test]
+    fn list_comprehension_qualifiers_are_aligned() {
+        let expected = indoc::indoc! {"
+        [ {X, Y}
+          || X <- L1,
+             Y <- L2,
+             pred(X, Y) ]"};
+        let texts = [
+            indoc::indoc! {"
+            [{X, Y} ||
+             X <- L1,
+             Y <- L2,
+             pred(X,Y)]"},
+            indoc::indoc! {"
+            [{X, Y}
+            ||X <- L1,
+            Y <- L2, pred(X, Y)]"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, expected, Expr);
+        }
+    }
+
+    #[test]
+    fn long_token_works() {
+        // Tokens wider than `max_columns` are never split and keep their own line.
+        let long_string = format!("\"{}\"", "x".repeat(150));
+        let long_atom = format!("'{}'", "y".repeat(150));
+        let text = format!("[foo,\n {long_string},\n <<{long_string}>>,\n {long_atom},\n bar]");
+        crate::assert_format!(text.as_str(), Expr);
+
+        let text = format!("[foo,\n     {long_string},\n  bar]");
+        let expected = format!("[foo,\n {long_string},\n bar]");
+        crate::assert_format!(text, expected, Expr);
+    }
+
+    #[test]
+    fn trailing_comma_fails() {
+        // Erlang accepts no trailing commas, so efmt never writes (nor accepts) them.
+        let texts = [
+            "[1,\n 2,\n]",
+            "{1,\n 2,\n}",
+            "#{a => 1,\n  b => 2,\n}",
+            "<<1,\n  2,\n>>",
+            "foo(1,\n    2,\n)",
+        ];
+        for text in texts {
+            assert!(crate::format_text::<Expr>(text).is_err(), "{text:?}");
+        }
+    }
 }
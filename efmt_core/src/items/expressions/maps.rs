@@ -9,6 +9,8 @@ use crate::span::Span;
 
 use super::components::ComprehensionExpr;
 
+// `MapExpr` is always boxed in `BaseExpr`, so boxing its variants as well is unnecessary.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Span, Parse, Format)]
 pub enum MapExpr {
     Construct(MapConstructExpr),
@@ -36,11 +38,19 @@ pub struct MapConstructExpr(MapLike<SharpSymbol, Expr>);
 /// `#` `{` `$ENTRY` || ([Qualifier] `,`?)+ `}`
 ///
 /// - $ENTRY: `Expr` `=>` `Expr`
-#[derive(Debug, Clone, Span, Parse, Format)]
+#[derive(Debug, Clone, Span, Format)]
 pub struct MapComprehensionExpr(
     ComprehensionExpr<(SharpSymbol, OpenBraceSymbol), CloseBraceSymbol, MapComprehensionValue>,
 );
 
+impl Parse for MapComprehensionExpr {
+    fn parse(ts: &mut parse::TokenStream) -> parse::Result<Self> {
+        let expr = Self(ts.parse()?);
+        ts.require_otp(26, "map comprehensions", &expr);
+        Ok(expr)
+    }
+}
+
 impl<Open, Close> ComprehensionExpr<Open, Close, MapComprehensionValue> {
     pub(crate) fn children(&self) -> impl Iterator<Item = &Expr> {
         std::iter::once(&self.value().key)
@@ -117,6 +127,86 @@ mod tests {
         }
     }
 
+    #[test]
+    fn packed_map_entries_work() {
+        let texts = [
+            indoc::indoc! {"
+            #{
+              a => 1, b => 2, c => 3,
+              d => {foo,
+                    bar},
+              e => 4, f => 5
+             }"},
+            indoc::indoc! {"
+            #{
+              a => 1, b => 2,
+              c => 3
+             }"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+            #{a => 1,   b => 2, c => 3, d => {foo,
+                                              bar}, e => 4}"};
+        let expected = indoc::indoc! {"
+            #{
+              a => 1, b => 2, c => 3,
+              d => {foo,
+                    bar},
+              e => 4
+             }"};
+        crate::assert_format!(text, expected, Expr);
+    }
+
+    #[test]
+    fn align_map_values_works() {
+        let text = indoc::indoc! {"
+            #{a => 1,
+              bbb := 2,
+              \"cc\" => [3,
+                       4],
+              {d, e} => 5}"};
+        let expected = indoc::indoc! {"
+            #{
+              a      => 1,
+              bbb    := 2,
+              \"cc\" => [3,
+                       4],
+              {d, e} => 5
+             }"};
+        let options = crate::format::FormatOptions::new().align_map_values(true);
+        crate::assert_format!(text, expected, Expr, options);
+        crate::assert_format!(expected, expected, Expr, options);
+
+        // Keys whose delimiter would exceed `max_columns` once aligned are not padded.
+        let options = options.max_columns(10);
+        let expected = indoc::indoc! {"
+            #{
+              a   => 1,
+              bbb := 2,
+              \"cc\" => [3,
+                       4],
+              {d, e} => 5
+             }"};
+        crate::assert_format!(text, expected, Expr, options);
+
+        // Single-line maps and packed entries are not affected.
+        let options = crate::format::FormatOptions::new().align_map_values(true);
+        let texts = [
+            "#{a => 1, bbb => 2}",
+            indoc::indoc! {"
+            #{
+              a => 1, bbb => 2,
+              cc => 3
+             }"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, text, Expr, options);
+        }
+    }
+
     #[test]
     fn map_comprehension_works() {
         let texts = [
@@ -0,0 +1,84 @@
+use crate::format::{Format, Formatter};
+use crate::items::components::Maybe;
+use crate::items::symbols::{GreaterSymbol, LessSymbol, SharpSymbol};
+use crate::items::tokens::{LexicalToken, VariableToken};
+use crate::parse::{self, Parse, TokenStream};
+use crate::span::Span;
+
+/// (`#` `$NAME`)? `<` `$TOKEN`* `>`
+///
+/// - $NAME: [VariableToken]
+/// - $TOKEN: [LexicalToken] except `>`
+///
+/// A printed representation of a pid, port, reference or function (e.g., `<0.123.0>` or `#Ref<0.1.2.3>`).
+/// This only appears in data files (e.g., logged terms) and is written verbatim.
+#[derive(Debug, Clone, Span)]
+pub struct OpaqueTermExpr {
+    name: Maybe<(SharpSymbol, VariableToken)>,
+    tokens: Vec<LexicalToken>,
+    close: GreaterSymbol,
+}
+
+impl OpaqueTermExpr {
+    /// Returns the name of this term (e.g., `"Ref"` for `#Ref<0.1.2.3>`).
+    pub fn name(&self) -> Option<&str> {
+        self.name.get().map(|(_, name)| name.value())
+    }
+
+    /// Returns the tokens between `<` and `>`.
+    pub fn tokens(&self) -> &[LexicalToken] {
+        &self.tokens
+    }
+}
+
+impl Parse for OpaqueTermExpr {
+    fn parse(ts: &mut TokenStream) -> parse::Result<Self> {
+        let name = ts.parse()?;
+        let _: LessSymbol = ts.parse()?;
+        let mut tokens = Vec::new();
+        while ts.peek::<GreaterSymbol>().is_none() {
+            tokens.push(ts.parse()?);
+        }
+        Ok(Self {
+            name,
+            tokens,
+            close: ts.parse()?,
+        })
+    }
+}
+
+impl Format for OpaqueTermExpr {
+    fn format(&self, fmt: &mut Formatter) {
+        fmt.write_span(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::items::Config;
+
+    #[test]
+    fn opaque_term_works() {
+        let texts = [
+            "<0.123.0>.\n",
+            "{ok, #Ref<0.1.2.3>}.\n",
+            "[#Port<0.5>, #Fun<erl_eval.6.123>].\n",
+            "#{pid => <0.1.0>}.\n",
+        ];
+        for text in texts {
+            crate::assert_format!(text, Config);
+        }
+
+        crate::assert_format!(
+            "{ <0.1.0> ,#Ref<0.1.2.3>}.",
+            "{<0.1.0>, #Ref<0.1.2.3>}.\n",
+            Config
+        );
+    }
+
+    #[test]
+    fn opaque_term_in_module_fails() {
+        let text = "foo() -> <0.1.0>.";
+        assert!(crate::format_text::<crate::items::Module>(text).is_err());
+    }
+}
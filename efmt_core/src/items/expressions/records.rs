@@ -170,6 +170,7 @@ impl Format for RecordField {
                 fmt.write_newline();
             } else {
                 fmt.write_space();
+                fmt.set_indent(fmt.column());
             }
             self.value.format(fmt);
         });
@@ -209,6 +210,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn record_field_value_works() {
+        let texts = [
+            "#rec{x = 1 + 2}",
+            indoc::indoc! {"
+            #rec{
+              field = A + B +
+                      C + D
+             }"},
+            indoc::indoc! {"
+            #rec{
+              field =
+                  A + B +
+                  C + D,
+              other = 1
+             }"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+        #rec{field = A+B+
+        C+D}"};
+        crate::assert_format!(text, texts[1], Expr);
+    }
+
     #[test]
     fn record_index_works() {
         let texts = ["#foo.bar"];
@@ -229,6 +257,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn record_access_chain_works() {
+        // A chain has no break points, so it's written in a single line even if it exceeds `max_columns`.
+        let chain = "Rec#rec5.rec4#rec4.rec3#rec3.rec2#rec2.rec1#rec1.name";
+        crate::assert_format!(chain, Expr);
+
+        let text = "Rec #rec5.rec4 #rec4.rec3#rec3 . rec2#rec2.rec1\n  #rec1.name";
+        crate::assert_format!(text, chain, Expr);
+
+        let text = format!("foo({chain})");
+        crate::assert_format!(text, Expr);
+    }
+
     #[test]
     fn record_update_works() {
         let texts = [
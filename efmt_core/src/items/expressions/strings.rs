@@ -75,4 +75,21 @@ mod tests {
             crate::assert_format!(text, Expr);
         }
     }
+
+    #[test]
+    fn multiline_string_works() {
+        // Line breaks and spaces inside a string literal are part of its value.
+        let texts = [
+            "\"line one\nstill inside\"",
+            "foo(\"line one  \n  still inside\",\n    bar)",
+            "[\"a\n\nb\"\n \"c\"]",
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = "foo(  \"line one\n  still inside\"  ,bar)";
+        let expected = "foo(\"line one\n  still inside\",\n    bar)";
+        crate::assert_format!(text, expected, Expr);
+    }
 }
@@ -63,4 +63,61 @@ mod tests {
             crate::assert_format!(text, Expr);
         }
     }
+
+    #[test]
+    fn nested_tuple_works() {
+        let texts = [
+            indoc::indoc! {"
+            {ok,
+             {state,
+              Count,
+              [a, b, c],
+              #{}}}"},
+            indoc::indoc! {"
+            {ok, {state, Count,
+                         [a, b, c],
+                         #{}}}"},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Expr);
+        }
+
+        let text = indoc::indoc! {"
+        {ok,
+        {state,
+        Count, [a,b,c], #{}}}"};
+        crate::assert_format!(text, texts[0], Expr);
+    }
+
+    #[test]
+    fn force_vertical_depth_works() {
+        let text = "{1, {2, {3, 4}, 5}, 6}";
+        let options = crate::format::FormatOptions::new().force_vertical_depth(3);
+        let expected = indoc::indoc! {"
+            {1,
+             {2,
+              {3,
+               4},
+              5},
+             6}"};
+        crate::assert_format!(text, expected, Expr, options);
+        crate::assert_format!(expected, expected, Expr, options);
+
+        // A single element is never broken, and the tag of a tagged tuple stays in the first line.
+        let text = "{1, {2}, {ok, {error, X}}}";
+        let options = crate::format::FormatOptions::new().force_vertical_depth(2);
+        crate::assert_format!(text, text, Expr, options);
+
+        // Not forced by default.
+        crate::assert_format!(text, text, Expr);
+
+        // The items containing a broken collection are laid out as multi-line in the same pass.
+        let text = "foo() -> {ok, {1, 2}}.";
+        let expected = indoc::indoc! {"
+            foo() ->
+                {ok, {1,
+                      2}}."};
+        crate::assert_format!(text, expected, crate::items::Form, options);
+        crate::assert_format!(expected, expected, crate::items::Form, options);
+    }
 }
@@ -4,7 +4,7 @@ use super::symbols::RightArrowSymbol;
 use crate::format::{Format, Formatter};
 use crate::items::atoms::{
     CallbackAtom, DefineAtom, ExportAtom, ExportTypeAtom, IncludeAtom, IncludeLibAtom, ModuleAtom,
-    OpaqueAtom, RecordAtom, SpecAtom, TypeAtom,
+    NominalAtom, OpaqueAtom, RecordAtom, SpecAtom, TypeAtom,
 };
 use crate::items::components::{
     Clauses, Either, Element, Items, Maybe, Never, NonEmptyItems, Null, Params, Parenthesized,
@@ -115,9 +115,10 @@ impl Format for RecordField {
         fmt.with_scoped_indent(|fmt| {
             self.name.format(fmt);
             if let Some((x, y)) = self.default.get() {
-                let newline = self.default.get().map_or(false, |(_, default_value)| {
-                    fmt.has_newline_until(default_value)
-                });
+                let newline = self
+                    .default
+                    .get()
+                    .is_some_and(|(_, default_value)| fmt.has_newline_until(default_value));
                 fmt.write_space();
                 x.format(fmt);
                 if newline {
@@ -125,6 +126,7 @@ impl Format for RecordField {
                     fmt.write_newline();
                 } else {
                     fmt.write_space();
+                    fmt.set_indent(fmt.column());
                 }
                 y.format(fmt);
             }
@@ -138,7 +140,7 @@ impl Format for RecordField {
     }
 }
 
-/// `-` (`type` | `opaque`) `$NAME` `(` (`$PARAM` `,`?)* `)` `::` `$TYPE` `.`
+/// `-` (`type` | `opaque` | `nominal`) `$NAME` `(` (`$PARAM` `,`?)* `)` `::` `$TYPE` `.`
 ///
 /// - $NAME: [AtomToken]
 /// - $PARAM: [VariableToken]
@@ -162,7 +164,7 @@ impl TypeDecl {
     }
 }
 
-type TypeDeclName = Either<TypeAtom, OpaqueAtom>;
+type TypeDeclName = Either<TypeAtom, Either<OpaqueAtom, NominalAtom>>;
 
 #[derive(Debug, Clone, Span, Parse)]
 struct TypeDeclItem {
@@ -220,7 +222,7 @@ impl FunSpec {
         }
     }
 
-    pub fn clauses(&self) -> impl Iterator<Item = SpecClauseRef> {
+    pub fn clauses(&self) -> impl Iterator<Item = SpecClauseRef<'_>> {
         match &self.0 {
             Either::A(x) => Either::A(x.value().clauses.iter().map(|x| SpecClauseRef {
                 params: x.params.get(),
@@ -325,14 +327,38 @@ impl<const INDENT: usize> Format for SpecClause<INDENT> {
 /// - $PARAM: [Expr]
 /// - $GUARD: ([Expr] (`,` | `;`)?)+
 /// - $BODY: ([Expr] `,`?)+
-#[derive(Debug, Clone, Span, Parse, Format)]
+#[derive(Debug, Clone, Span, Parse)]
 pub struct FunDecl {
     clauses: Clauses<FunctionClause<AtomToken>>,
     dot: DotSymbol,
 }
 
+impl Format for FunDecl {
+    fn format(&self, fmt: &mut Formatter) {
+        if fmt.options().blank_line_between_clauses && fmt.contains_newline(&self.clauses) {
+            fmt.with_scoped_indent(|fmt| {
+                fmt.set_indent(fmt.column());
+
+                let mut delimiters = self.clauses.delimiters().iter();
+                for (i, clause) in self.clauses.iter().enumerate() {
+                    if i > 0 {
+                        if let Some(delimiter) = delimiters.next() {
+                            delimiter.format(fmt);
+                        }
+                        fmt.write_newlines(2);
+                    }
+                    clause.format(fmt);
+                }
+            });
+        } else {
+            self.clauses.format(fmt);
+        }
+        self.dot.format(fmt);
+    }
+}
+
 impl FunDecl {
-    pub fn clauses(&self) -> impl Iterator<Item = FunctionClauseRef> {
+    pub fn clauses(&self) -> impl Iterator<Item = FunctionClauseRef<'_>> {
         self.clauses.iter().map(|x| FunctionClauseRef {
             name: x.name(),
             params: x.params(),
@@ -351,7 +377,7 @@ pub struct FunctionClauseRef<'a> {
 }
 
 impl<'a> FunctionClauseRef<'a> {
-    pub fn function_name(&self) -> &AtomToken {
+    pub fn function_name(&self) -> &'a AtomToken {
         self.name
     }
 
@@ -367,27 +393,54 @@ impl<'a> FunctionClauseRef<'a> {
     }
 }
 
-/// `-` `module` `(` [AtomToken] `)` `.`
+/// `-` `module` `(` [AtomToken] (`,` `$PARAMS`)? `)` `.`
+///
+/// - $PARAMS: [Expr] (the parameter list of a legacy parameterized module, e.g., `[A, B]`)
 #[derive(Debug, Clone, Span, Format)]
-pub struct ModuleAttr(AttrLike<ModuleAtom, AtomToken>);
+pub struct ModuleAttr(AttrLike<ModuleAtom, ModuleAttrValue>);
 
 impl ModuleAttr {
     pub fn module_name(&self) -> &AtomToken {
-        self.0.value()
+        &self.0.value().name
+    }
+
+    /// Returns the parameter list if this is a (deprecated) parameterized module.
+    pub fn params(&self) -> Option<&Expr> {
+        self.0.value().params.get().map(|(_, x)| x)
     }
 }
 
 impl Parse for ModuleAttr {
     fn parse(ts: &mut TokenStream) -> crate::parse::Result<Self> {
-        let attr = AttrLike::<ModuleAtom, AtomToken>::parse(ts)?;
+        let attr = AttrLike::<ModuleAtom, ModuleAttrValue>::parse(ts)?;
         if ts.filepath().is_none() {
-            let module_name = attr.value().value();
+            let module_name = attr.value().name.value();
             ts.set_filepath(format!("{module_name}.erl"));
         }
         Ok(ModuleAttr(attr))
     }
 }
 
+#[derive(Debug, Clone, Span, Parse)]
+struct ModuleAttrValue {
+    name: AtomToken,
+    params: Maybe<(CommaSymbol, Expr)>,
+}
+
+impl Format for ModuleAttrValue {
+    fn format(&self, fmt: &mut Formatter) {
+        fmt.with_scoped_indent(|fmt| {
+            fmt.set_indent(fmt.column());
+            self.name.format(fmt);
+            if let Some((comma, params)) = self.params.get() {
+                comma.format(fmt);
+                fmt.write_space();
+                params.format(fmt);
+            }
+        });
+    }
+}
+
 /// `-` `export|export_type` `$EXPORTS` `.`
 ///
 /// - $EXPORTS: `(` (`$EXPORT`,`?)* `)`
@@ -527,7 +580,7 @@ impl<Name: Format, Value: Format, Empty: Format> Format for AttrLike<Name, Value
             self.dot.format(fmt);
         };
 
-        if self.contains_newline() {
+        if fmt.contains_newline(self) {
             f(fmt);
         } else {
             fmt.with_single_line_mode(f);
@@ -664,6 +717,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn file_directive_works() {
+        // `-file` is handled as a generic attribute, and its line number is kept as-is.
+        let texts = [
+            r#"-file("src/foo_parser.yrl", 10)."#,
+            r#"-file("/usr/lib/erlang/lib/parsetools/include/yeccpre.hrl", 0)."#,
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+
+        crate::assert_format!(r#"-file( "src/foo_parser.yrl",10 )."#, texts[0], Form);
+    }
+
     #[test]
     fn other_directive_works() {
         let texts = [
@@ -674,10 +741,14 @@ mod tests {
             "-endif.",
             "-if(true).",
             "-elif(true).",
+            "-if(?OTP_RELEASE >= 25).",
+            "-elif(?OTP_RELEASE >= 23 andalso defined(TEST)).",
         ];
         for text in texts {
             crate::assert_format!(text, Form);
         }
+
+        crate::assert_format!("-if( ?OTP_RELEASE>=25 ).", "-if(?OTP_RELEASE >= 25).", Form);
     }
 
     #[test]
@@ -705,6 +776,114 @@ mod tests {
         }
     }
 
+    #[test]
+    fn custom_attr_works() {
+        // Attributes with unknown names are formatted as generic attributes.
+        let texts = [
+            "-my_custom_attr([{a, 1}, {b, 2}]).",
+            indoc::indoc! {"
+            -my_custom_attr([{a, 1},
+                             {b, 2}])."},
+            "-my_attr(#{key => value}).",
+            "-my_attr(foo, \"bar\", 1.0).",
+            "-my_attr [a, b].",
+            "-'my attr'(foo).",
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+
+        let text = indoc::indoc! {"
+        -my_custom_attr( [{a,1},
+        {b,2}] )."};
+        crate::assert_format!(text, texts[1], Form);
+    }
+
+    #[test]
+    fn export_type_attr_works() {
+        let texts = [
+            "-export_type([t/0, u/1]).",
+            indoc::indoc! {"
+            -export_type([alpha/0,
+                          beta/1,
+                          gamma/2,
+                          delta/3])."},
+            indoc::indoc! {"
+            -export_type([t/0, t/1,
+                          u/0])."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+
+        let text = indoc::indoc! {"
+        -export_type( [alpha/0, beta/1,
+        gamma/2, delta/3] )."};
+        crate::assert_format!(text, texts[1], Form);
+    }
+
+    #[test]
+    fn compile_attr_works() {
+        let texts = [
+            "-compile(export_all).",
+            "-compile({inline, [f/1, g/2]}).",
+            indoc::indoc! {"
+            -compile({inline, [f/1,
+                               g/2,
+                               h/3]})."},
+            indoc::indoc! {"
+            -compile([{inline, [f/1,
+                                g/2]},
+                      {nowarn_unused_function, [{f, 1}]}])."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+
+        let text = indoc::indoc! {"
+        -compile( {inline,[f/1,
+        g/2, h/3 ]} )."};
+        crate::assert_format!(text, texts[2], Form);
+    }
+
+    #[test]
+    fn module_attr_works() {
+        let texts = [
+            "-module(foo).",
+            "-module(foo, [A, B]).",
+            indoc::indoc! {"
+            -module(foo, [Alpha,
+                          Beta])."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+
+        crate::assert_format!("-module( foo ,[A,B] ).", texts[1], Form);
+    }
+
+    #[test]
+    fn behaviour_attrs_work() {
+        let texts = [
+            "-behaviour(gen_server).",
+            "-behavior(gen_statem).",
+            "-optional_callbacks([foo/1, bar/2]).",
+            indoc::indoc! {"
+            -optional_callbacks([foo/1,
+                                 bar/2,
+                                 baz/3,
+                                 qux/0])."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+
+        let text = indoc::indoc! {"
+        -optional_callbacks([foo/1, bar/2,
+        baz/3, qux/0])."};
+        crate::assert_format!(text, texts[3], Form);
+    }
+
     #[test]
     fn record_decl_works() {
         let texts = [
@@ -739,12 +918,43 @@ mod tests {
                      })."},
             indoc::indoc! {"
             -record(rec, {field1 = [] :: Type1, field2, field3 = 421})."},
+            indoc::indoc! {"
+            -record(r, {
+                      field = A + B +
+                              C + D
+                     })."},
         ];
         for text in texts {
             crate::assert_format!(text, Form);
         }
     }
 
+    #[test]
+    fn record_field_comment_works() {
+        let expected = indoc::indoc! {"
+            -record(foo, {
+                      a = 0 :: integer(),  % count
+                      b,
+                      c = [] :: list()  % items
+                     })."};
+        let texts = [
+            indoc::indoc! {"
+            -record(foo, {a = 0 :: integer(), % count
+                          b,
+                          c = [] :: list() % items
+                         })."},
+            indoc::indoc! {"
+            -record(foo, {
+                a = 0 :: integer(),    % count
+                b, c = [] :: list()  % items
+            })."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, expected, Form);
+        }
+        crate::assert_format!(expected, Form);
+    }
+
     #[test]
     fn fun_decl_works() {
         let texts = [
@@ -776,6 +986,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn blank_line_between_clauses_works() {
+        let text = indoc::indoc! {"
+        foo(a) ->
+            case X of
+                1 -> a;
+                2 -> b
+            end;
+        foo(b) -> 2."};
+        let expected = indoc::indoc! {"
+        foo(a) ->
+            case X of
+                1 -> a;
+                2 -> b
+            end;
+
+        foo(b) -> 2."};
+        let options = crate::format::FormatOptions::new().blank_line_between_clauses(true);
+        crate::assert_format!(text, expected, Form, options);
+        crate::assert_format!(expected, expected, Form, options);
+
+        // Single-line definitions are kept as-is.
+        let text = "foo(a) -> 1; foo(b) -> 2.";
+        crate::assert_format!(text, text, Form, options);
+
+        // No blank lines are inserted by default.
+        crate::assert_format!(text, text, Form);
+    }
+
+    #[test]
+    fn comment_between_fun_clauses_works() {
+        let text = indoc::indoc! {"
+        foo(a) ->
+            1;
+        % note
+        foo(b) ->
+            2;
+            % indented note
+        foo(c) -> 3;  % trailing
+        foo(_) -> 4."};
+        let expected = indoc::indoc! {"
+        foo(a) ->
+            1;
+        % note
+        foo(b) ->
+            2;
+        % indented note
+        foo(c) -> 3;  % trailing
+        foo(_) -> 4."};
+        crate::assert_format!(text, expected, Form);
+    }
+
     #[test]
     fn fun_spec_works() {
         let texts = [
@@ -823,6 +1085,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn same_name_fun_specs_work() {
+        let text = indoc::indoc! {"
+            -spec f(integer())->integer().
+            f(X)->f(X,1).
+            -spec f(integer(),integer())->integer().
+            f(X,Y)->X+Y.
+            "};
+        let expected = indoc::indoc! {"
+            -spec f(integer()) -> integer().
+            f(X) -> f(X, 1).
+
+
+            -spec f(integer(), integer()) -> integer().
+            f(X, Y) -> X + Y.
+            "};
+        crate::assert_format!(text, expected, crate::items::Module);
+    }
+
     #[test]
     fn type_decl_works() {
         let texts = [
@@ -842,9 +1123,66 @@ mod tests {
                             Val) ::
                       [{Key,
                         Val}]."},
+            "-nominal meter() :: integer().",
+            indoc::indoc! {"
+            -nominal pair(A,
+                          B) ::
+                       {A, B}."},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Form);
+        }
+    }
+
+    #[test]
+    fn spec_return_map_works() {
+        let expected = indoc::indoc! {"
+        -spec f() -> #{
+                       a => integer(),
+                       b => atom(),
+                       c => binary()
+                      }."};
+        crate::assert_format!(expected, Form);
+
+        let text = indoc::indoc! {"
+        -spec f() -> #{a => integer(),
+        b => atom(), c => binary()}."};
+        crate::assert_format!(text, expected, Form);
+
+        // The return type wraps independently of the argument types.
+        let text = indoc::indoc! {"
+        -spec f(#{a => integer()}, atom()) -> #{a => integer(),
+        b => atom(), c => binary()}."};
+        let expected = indoc::indoc! {"
+        -spec f(#{a => integer()}, atom()) -> #{
+                                                a => integer(),
+                                                b => atom(),
+                                                c => binary()
+                                               }."};
+        crate::assert_format!(text, expected, Form);
+    }
+
+    #[test]
+    fn map_type_decl_works() {
+        let texts = [
+            "-type t() :: #{}.",
+            "-type t() :: #{atom() => term(), id := integer()}.",
+            indoc::indoc! {"
+            -type t() :: #{
+                           atom() => term(),
+                           id := integer()
+                          }."},
         ];
         for text in texts {
             crate::assert_format!(text, Form);
         }
+
+        let text = indoc::indoc! {"
+        -type t()::#{atom()=>term(),
+        id:=integer()}."};
+        crate::assert_format!(text, texts[2], Form);
+
+        // Unlike Elixir, Erlang has no `...` in map types.
+        assert!(crate::format_text::<Form>("-type t() :: #{atom() => term(), ...}.").is_err());
     }
 }
@@ -118,6 +118,14 @@ impl MacroName {
             Either::B(x) => x.value(),
         }
     }
+
+    pub(crate) fn from_token(token: &LexicalToken) -> Option<Self> {
+        match token {
+            LexicalToken::Atom(x) => Some(Self(Either::A(x.clone()))),
+            LexicalToken::Variable(x) => Some(Self(Either::B(x.clone()))),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -169,7 +177,7 @@ impl Format for MacroReplacement {
             .parse_tokens::<Expr>(self.tokens.clone())
         {
             if expr.end_position() == self.end_position() {
-                expr.format(fmt);
+                fmt.with_parens_kept(|fmt| expr.format(fmt));
                 return;
             }
         }
@@ -244,12 +252,11 @@ impl Parse for MacroArg {
                     | Keyword::Receive => {
                         level += 1;
                     }
-                    Keyword::Fun => {
-                        if ts.peek::<OpenParenSymbol>().is_some()
-                            || ts.peek::<(LexicalToken, OpenParenSymbol)>().is_some()
-                        {
-                            level += 1;
-                        }
+                    Keyword::Fun
+                        if (ts.peek::<OpenParenSymbol>().is_some()
+                            || ts.peek::<(LexicalToken, OpenParenSymbol)>().is_some()) =>
+                    {
+                        level += 1;
                     }
                     Keyword::End => {
                         if level == 0 {
@@ -275,7 +282,7 @@ impl Format for MacroArg {
             .parse_tokens::<Expr>(self.tokens.clone())
         {
             if expr.end_position() == self.end_position() {
-                expr.format(fmt);
+                fmt.with_parens_kept(|fmt| expr.format(fmt));
                 return;
             }
         }
@@ -349,6 +356,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn predefined_macro_works() {
+        let texts = [indoc::indoc! {"
+            -module(foo).
+            -vsn(?VSN).
+
+
+            foo() ->
+                io:format(\"~p~n\", [?MODULE]),
+                {?LINE, ?FILE, ?FUNCTION_NAME, ?FUNCTION_ARITY, ?OTP_RELEASE},
+                \"module: \"
+                ?MODULE_STRING.
+            "}];
+        for text in texts {
+            crate::assert_format!(text, Module);
+        }
+    }
+
     #[test]
     fn macro_with_args_works() {
         let texts = [
@@ -603,4 +628,123 @@ mod tests {
             crate::assert_format!(text, Module);
         }
     }
+
+    #[test]
+    fn macro_call_is_not_expanded() {
+        let texts = [
+            indoc::indoc! {"
+            foo() ->
+                ?MODULE.
+            "},
+            indoc::indoc! {"
+            -include_lib(\"eunit/include/eunit.hrl\").
+
+
+            foo(X) ->
+                ?assert(X),
+                ?LOG(info, \"msg\").
+            "},
+            indoc::indoc! {"
+            -define(assert(X), case X of true -> ok end).
+
+
+            foo(X) ->
+                ?assert(X).
+            "},
+        ];
+        for text in texts {
+            crate::assert_format!(text, Module);
+        }
+    }
+
+    #[test]
+    fn macro_in_call_position_works() {
+        let texts = [indoc::indoc! {"
+            -define(SERVER, ?MODULE).
+            -define(MOD,    foo).
+            -define(FUN,    bar).
+
+
+            foo() ->
+                ?MODULE:start(),
+                ?MOD:?FUN(),
+                ?MOD:bar(?MODULE),
+                gen_server:call(?SERVER, ping),
+                fun ?MODULE:foo/0.
+            "}];
+        for text in texts {
+            crate::assert_format!(text, Module);
+        }
+    }
+
+    #[test]
+    fn macro_in_function_head_works() {
+        let texts = [indoc::indoc! {"
+            -define(DEFAULT, default).
+            -define(PAIR(A, B), {A, B}).
+            -define(ARGS, A, B).
+
+
+            foo(?DEFAULT) ->
+                ok;
+            foo(?PAIR(X, Y)) when X =:= ?DEFAULT ->
+                Y.
+
+
+            bar(?PAIR(Xxxxxxxxxxxxxxxx, Yyyyyyyyyyyyyyyyyyyy),
+                ?DEFAULT) ->
+                Xxxxxxxxxxxxxxxx.
+
+
+            baz(?ARGS) ->
+                A + B.
+            "}];
+        for text in texts {
+            crate::assert_format!(text, Module);
+        }
+
+        // Macros in a head are kept as-is rather than being expanded.
+        let text = indoc::indoc! {"
+            -define(DEFAULT, default).
+            foo( ?DEFAULT )->ok."};
+        let expected = indoc::indoc! {"
+            -define(DEFAULT, default).
+
+
+            foo(?DEFAULT) -> ok.
+            "};
+        crate::assert_format!(text, expected, Module);
+    }
+
+    #[test]
+    fn stringify_macro_works() {
+        let texts = [indoc::indoc! {"
+            -define(show(X), ??X).
+            -define(eq(A, B), {??A, A, ??B, B}).
+
+
+            foo() ->
+                ?show(foo),
+                ?show(1 + 2),
+                ?eq(length([a]), 1).
+            "}];
+        for text in texts {
+            crate::assert_format!(text, Module);
+        }
+
+        // Usages are not expanded, and replacements containing `??` are kept as-is
+        // because they cannot be parsed as expressions.
+        let text = indoc::indoc! {"
+            -define(show(X),??X).
+            -define(eq(A,B),{??A,A,??B,B}).
+            foo()->?show( foo ),?eq(length([a]),1)."};
+        let expected = indoc::indoc! {"
+            -define(show(X), ??X).
+            -define(eq(A, B), {??A,A,??B,B}).
+
+
+            foo() -> ?show(foo), ?eq(length([a]), 1).
+            "};
+        crate::assert_format!(text, expected, Module);
+    }
 }
@@ -1,11 +1,14 @@
 use super::components::Either;
 use super::tokens::LexicalToken;
 use crate::format::{Format, Formatter};
-use crate::items::forms::DefineDirective;
+use crate::items::forms::{DefineDirective, IncludeDirective};
+use crate::items::macros::MacroName;
 use crate::items::{forms, Form};
+use crate::parse::token_stream::is_predefined_macro;
 use crate::parse::{self, Parse, TokenStream};
 use crate::span::{Position, Span};
 use erl_tokenize::values::Symbol;
+use std::collections::HashSet;
 
 /// [Form]*
 #[derive(Debug, Clone, Span)]
@@ -22,6 +25,71 @@ impl<const ALLOW_PARTIAL_FAILURE: bool> Module<ALLOW_PARTIAL_FAILURE> {
             Either::B(_) => None,
         })
     }
+
+    /// Returns the include directives and the macros that are used but not defined in this module.
+    ///
+    /// `ts` must be the token stream this module was parsed from
+    /// (macro calls are expanded while parsing, so they are only recorded in the token stream).
+    pub fn dependencies(&self, ts: &TokenStream) -> Dependencies {
+        let mut includes = Vec::new();
+        let mut defined = HashSet::new();
+        let mut used = Vec::new();
+        for form in self.children() {
+            match form.get() {
+                forms::Form::Include(x) => includes.push(x.clone()),
+                forms::Form::Define(x) => {
+                    defined.insert(x.macro_name().to_owned());
+
+                    // Macros in a replacement are only recorded in `ts` when the defined macro is used.
+                    let tokens = x.replacement();
+                    for (question, name) in tokens.iter().zip(tokens.iter().skip(1)) {
+                        if !matches!(question, LexicalToken::Symbol(x) if x.value() == Symbol::Question)
+                        {
+                            continue;
+                        }
+                        if let Some(name) = MacroName::from_token(name) {
+                            used.push(name);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        used.extend(ts.macros().values().map(|x| x.macro_name().clone()));
+        used.sort_by_key(|x| x.start_position());
+        used.dedup_by_key(|x| x.start_position());
+        used.retain(|x| !defined.contains(x.value()) && !is_predefined_macro(x.value()));
+        Dependencies {
+            includes,
+            undefined_macros: used,
+        }
+    }
+}
+
+/// Files and macros that a [Module] depends on (see [Module::dependencies()]).
+#[derive(Debug, Clone)]
+pub struct Dependencies {
+    /// `-include` and `-include_lib` directives in the order of appearance.
+    pub includes: Vec<IncludeDirective>,
+
+    /// Usages of the macros that are not defined in the module, in the order of appearance.
+    ///
+    /// Predefined macros such as `?MODULE` are excluded.
+    pub undefined_macros: Vec<MacroName>,
+}
+
+impl Dependencies {
+    /// Returns the names of [Dependencies::undefined_macros] without duplicates.
+    pub fn undefined_macro_names(&self) -> Vec<&str> {
+        let mut names = self
+            .undefined_macros
+            .iter()
+            .map(|x| x.value())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
 }
 
 impl<const ALLOW_PARTIAL_FAILURE: bool> Parse for Module<ALLOW_PARTIAL_FAILURE> {
@@ -68,14 +136,16 @@ impl<const ALLOW_PARTIAL_FAILURE: bool> Parse for Module<ALLOW_PARTIAL_FAILURE>
 impl<const ALLOW_PARTIAL_FAILURE: bool> Format for Module<ALLOW_PARTIAL_FAILURE> {
     fn format(&self, fmt: &mut Formatter) {
         let mut state = FormatState {
-            is_last_spec: false,
+            last_spec: None,
             pending_constants: Vec::new(),
+            attributes_seen: false,
+            definitions_seen: false,
         };
         let mut is_last_fun_decl = false;
 
         for form in &self.forms {
             if is_last_fun_decl {
-                fmt.write_newlines(3);
+                fmt.write_newlines(fmt.options().newline_between_functions + 1);
                 is_last_fun_decl = false;
             }
 
@@ -88,6 +158,7 @@ impl<const ALLOW_PARTIAL_FAILURE: bool> Format for Module<ALLOW_PARTIAL_FAILURE>
                 }
             };
 
+            let is_end_of_attributes = state.is_end_of_attributes(form);
             if state.pend_if_need(fmt, form) {
                 continue;
             }
@@ -96,7 +167,7 @@ impl<const ALLOW_PARTIAL_FAILURE: bool> Format for Module<ALLOW_PARTIAL_FAILURE>
                 continue;
             }
 
-            state.insert_two_empty_newlines_if_need(fmt, form);
+            state.insert_two_empty_newlines_if_need(fmt, form, is_end_of_attributes);
 
             form.format(fmt);
             fmt.write_newline();
@@ -108,8 +179,10 @@ impl<const ALLOW_PARTIAL_FAILURE: bool> Format for Module<ALLOW_PARTIAL_FAILURE>
 }
 
 struct FormatState<'a> {
-    is_last_spec: bool,
+    last_spec: Option<&'a Form>,
     pending_constants: Vec<&'a DefineDirective>,
+    attributes_seen: bool,
+    definitions_seen: bool,
 }
 
 impl<'a> FormatState<'a> {
@@ -163,16 +236,64 @@ impl<'a> FormatState<'a> {
         }
     }
 
-    fn insert_two_empty_newlines_if_need(&mut self, fmt: &mut Formatter, form: &'a Form) {
-        if form.is_func_decl() && !self.is_last_spec {
-            fmt.flush_non_preceding_comments(form);
-            fmt.write_newlines(3);
+    // Returns `true` if `form` is the first function, spec or type declaration after the leading attributes.
+    fn is_end_of_attributes(&mut self, form: &Form) -> bool {
+        let is_definition = matches!(
+            form.get(),
+            forms::Form::FunSpec(_) | forms::Form::FunDecl(_) | forms::Form::TypeDecl(_)
+        );
+        let is_end = is_definition && self.attributes_seen && !self.definitions_seen;
+        self.attributes_seen |= !is_definition;
+        self.definitions_seen |= is_definition;
+        is_end
+    }
+
+    fn insert_two_empty_newlines_if_need(
+        &mut self,
+        fmt: &mut Formatter,
+        form: &'a Form,
+        is_end_of_attributes: bool,
+    ) {
+        let mut newlines = fmt.options().newline_between_functions + 1;
+        if is_end_of_attributes && fmt.options().blank_line_after_attributes {
+            newlines = 2;
+            if !form.is_func_decl() && !form.is_func_spec() {
+                fmt.flush_non_preceding_comments(form);
+                fmt.write_newlines(newlines);
+            }
+        }
+
+        if form.is_func_decl() {
+            match self.last_spec {
+                Some(spec) if Self::is_spec_of(spec, form) => {
+                    // A spec and its function are always glued together.
+                    if !fmt
+                        .token_stream()
+                        .contains_comment(&(spec.end_position(), form.start_position()))
+                    {
+                        fmt.skip_next_blank_line();
+                    }
+                }
+                _ => {
+                    fmt.flush_non_preceding_comments(form);
+                    fmt.write_newlines(newlines);
+                }
+            }
         }
 
-        self.is_last_spec = form.is_func_spec();
+        self.last_spec = form.is_func_spec().then_some(form);
         if form.is_func_spec() {
             fmt.flush_non_preceding_comments(form);
-            fmt.write_newlines(3);
+            fmt.write_newlines(newlines);
+        }
+    }
+}
+
+impl FormatState<'_> {
+    fn is_spec_of(spec: &Form, fun: &Form) -> bool {
+        match (spec.func_name_and_arity(), fun.func_name_and_arity()) {
+            (Some(x), Some(y)) => x == y,
+            _ => true,
         }
     }
 }
@@ -182,3 +303,272 @@ struct Skipped {
     start: Position,
     end: Position,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+
+    #[test]
+    fn spec_is_glued_to_its_function() {
+        let text = indoc::indoc! {"
+            -spec foo() -> ok.
+
+            foo() -> ok.
+            -spec bar(term()) -> ok.
+            bar(_) -> ok.
+            -spec baz() -> ok.
+            qux() -> ok.
+            -spec quux() -> ok.
+
+            %% comment
+            quux() -> ok.
+            "};
+        let expected = indoc::indoc! {"
+            -spec foo() -> ok.
+            foo() -> ok.
+
+
+            -spec bar(term()) -> ok.
+            bar(_) -> ok.
+
+
+            -spec baz() -> ok.
+
+
+            qux() -> ok.
+
+
+            -spec quux() -> ok.
+
+            %% comment
+            quux() -> ok.
+            "};
+        crate::assert_format!(text, expected, Module);
+
+        let options = FormatOptions::new().newline_between_functions(1);
+        let expected = indoc::indoc! {"
+            -spec foo() -> ok.
+            foo() -> ok.
+            "};
+        crate::assert_format!(
+            &text[..text.find("-spec bar").unwrap()],
+            expected,
+            Module,
+            options
+        );
+    }
+
+    #[test]
+    fn newline_between_functions_works() {
+        let text = indoc::indoc! {"
+            -module(foo).
+            -export([foo/0, bar/0]).
+            -spec foo() -> ok.
+            foo() -> ok.
+            bar() -> ok.
+            -define(BAZ, baz).
+            "};
+        let expected = indoc::indoc! {"
+            -module(foo).
+            -export([foo/0, bar/0]).
+
+            -spec foo() -> ok.
+            foo() -> ok.
+
+            bar() -> ok.
+
+            -define(BAZ, baz).
+            "};
+        let options = FormatOptions::new().newline_between_functions(1);
+        crate::assert_format!(text, expected, Module, options);
+
+        let expected = indoc::indoc! {"
+            -module(foo).
+            -export([foo/0, bar/0]).
+            -spec foo() -> ok.
+            foo() -> ok.
+            bar() -> ok.
+            -define(BAZ, baz).
+            "};
+        let options = FormatOptions::new().newline_between_functions(0);
+        crate::assert_format!(text, expected, Module, options);
+    }
+
+    #[test]
+    fn eof_comment_works() {
+        let texts = [
+            "foo() -> ok.\n\n%% trailing note\n",
+            "foo() -> ok.\n%% trailing note\n",
+            "foo() -> ok.\n\n%% a\n\n%% b\n",
+            "foo() -> ok.  % trailing\n%% note\n",
+        ];
+        for text in texts {
+            crate::assert_format!(text, Module);
+        }
+
+        // Comments after the last form are written at column 0 with exactly one trailing newline.
+        crate::assert_format!(
+            "foo() -> ok.\n\n    %% indented note\n\n\n",
+            texts[0].replace("trailing", "indented"),
+            Module
+        );
+        crate::assert_format!("foo() -> ok.\n\n%% trailing note", texts[0], Module);
+    }
+
+    #[test]
+    fn escript_works() {
+        let text = indoc::indoc! {"
+            #!/usr/bin/env escript
+            %%! -smp enable -sname factorial
+            main([S])->io:format(\"~p~n\",[S]).
+            "};
+        let expected = indoc::indoc! {"
+            #!/usr/bin/env escript
+            %%! -smp enable -sname factorial
+            main([S]) -> io:format(\"~p~n\", [S]).
+            "};
+        crate::assert_format!(text, expected, Module);
+
+        let text = indoc::indoc! {"
+            #!/usr/bin/env escript
+            -module(foo).
+            -export([main/1]).
+
+
+            main(_) -> ok.
+            "};
+        crate::assert_format!(text, Module);
+
+        // `#!` is only special on the first line.
+        assert!(crate::format_text::<Module>("foo() -> ok.\n#!/usr/bin/env escript\n").is_err());
+    }
+
+    #[test]
+    fn dependencies_works() {
+        let text = indoc::indoc! {r#"
+            -module(foo).
+            -include("foo.hrl").
+            -include_lib("kernel/include/file.hrl").
+            -define(LOCAL, ?FROM_DEFINE + 1).
+            -define(UNUSED, ?UNUSED_IN_DEFINE).
+
+            foo(X) ->
+                {?MODULE, ?LOCAL, ?EXTERNAL(X), ?EXTERNAL, ?Var}.
+            "#};
+        let mut ts = TokenStream::new(erl_tokenize::Tokenizer::new(text.to_owned()));
+        let module: Module = ts.parse().unwrap();
+        let deps = module.dependencies(&ts);
+
+        let includes = deps
+            .includes
+            .iter()
+            .map(|x| (x.is_include_lib(), x.include_path().value()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            includes,
+            [(false, "foo.hrl"), (true, "kernel/include/file.hrl")]
+        );
+
+        let macros = deps
+            .undefined_macros
+            .iter()
+            .map(|x| (x.value(), x.start_position().line()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            macros,
+            [
+                ("FROM_DEFINE", 4),
+                ("UNUSED_IN_DEFINE", 5),
+                ("EXTERNAL", 8),
+                ("EXTERNAL", 8),
+                ("Var", 8)
+            ]
+        );
+        assert_eq!(
+            deps.undefined_macro_names(),
+            ["EXTERNAL", "FROM_DEFINE", "UNUSED_IN_DEFINE", "Var"]
+        );
+    }
+
+    #[test]
+    fn blank_line_after_attributes_works() {
+        let options = FormatOptions::new().blank_line_after_attributes(true);
+        let text = indoc::indoc! {"
+            -module(foo).
+            -export([foo/0]).
+            -include(\"foo.hrl\").
+            foo() -> ok.
+            bar() -> ok.
+            "};
+        let expected = indoc::indoc! {"
+            -module(foo).
+            -export([foo/0]).
+            -include(\"foo.hrl\").
+
+            foo() -> ok.
+
+
+            bar() -> ok.
+            "};
+        crate::assert_format!(text, expected, Module, options);
+        crate::assert_format!(expected, expected, Module, options);
+
+        // Extra empty lines are removed.
+        let text = indoc::indoc! {"
+            -module(foo).
+
+
+
+            -spec foo() -> ok.
+            foo() -> ok.
+            "};
+        let expected = indoc::indoc! {"
+            -module(foo).
+
+            -spec foo() -> ok.
+            foo() -> ok.
+            "};
+        crate::assert_format!(text, expected, Module, options);
+
+        // Type declarations are also definitions.
+        let text = indoc::indoc! {"
+            -module(foo).
+            -type t() :: ok.
+            -export_type([t/0]).
+            -type u() :: ok.
+            "};
+        let expected = indoc::indoc! {"
+            -module(foo).
+
+            -type t() :: ok.
+            -export_type([t/0]).
+            -type u() :: ok.
+            "};
+        crate::assert_format!(text, expected, Module, options);
+
+        // Comments are placed in the same way as `newline_between_functions`.
+        let text = indoc::indoc! {"
+            -module(foo).
+            %% comment
+            foo() -> ok.
+            "};
+        let expected = indoc::indoc! {"
+            -module(foo).
+            %% comment
+
+            foo() -> ok.
+            "};
+        crate::assert_format!(text, expected, Module, options);
+
+        // Disabled by default.
+        let expected = indoc::indoc! {"
+            -module(foo).
+            %% comment
+
+
+            foo() -> ok.
+            "};
+        crate::assert_format!(text, expected, Module);
+    }
+}
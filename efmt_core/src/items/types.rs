@@ -493,6 +493,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn any_arity_function_works() {
+        // `...` is a single token, so no spaces are written inside it.
+        let texts = [
+            ("fun((...)->ok)", "fun((...) -> ok)"),
+            ("fun( ( ... ) -> ok )", "fun((...) -> ok)"),
+            ("[fun((...) -> T)]", "[fun((...) -> T)]"),
+        ];
+        for (text, expected) in texts {
+            crate::assert_format!(text, expected, Type);
+        }
+
+        let text = "-spec foo(fun((...)->ok))->fun((...)->term()).";
+        let expected = "-spec foo(fun((...) -> ok)) -> fun((...) -> term()).";
+        crate::assert_format!(text, expected, crate::items::forms::Form);
+
+        // `. . .` is not `...`.
+        assert!(crate::format_text::<Type>("fun((. . .) -> ok)").is_err());
+    }
+
     #[test]
     fn unary_op_works() {
         let texts = ["-10", "+10", "bnot 100", "- - + +3"];
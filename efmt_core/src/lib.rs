@@ -6,13 +6,19 @@ pub mod span;
 
 pub fn format_text<T: crate::parse::Parse + crate::format::Format>(
     text: &str,
+) -> crate::parse::Result<String> {
+    format_text_with_options::<T>(text, &crate::format::FormatOptions::default())
+}
+
+pub fn format_text_with_options<T: crate::parse::Parse + crate::format::Format>(
+    text: &str,
+    options: &crate::format::FormatOptions,
 ) -> crate::parse::Result<String> {
     let tokenizer = erl_tokenize::Tokenizer::new(text.to_owned());
     let mut ts = crate::parse::TokenStream::new(tokenizer);
     let item: T = ts.parse()?;
-    let mut formatter = crate::format::Formatter::new(ts);
-    item.format(&mut formatter);
-    let formatted_text = formatter.finish();
+    let formatter = crate::format::Formatter::with_options(ts, options.clone());
+    let formatted_text = formatter.format_all(&item);
     Ok(formatted_text)
 }
 
@@ -20,13 +26,18 @@ pub fn format_text<T: crate::parse::Parse + crate::format::Format>(
 #[macro_export]
 macro_rules! assert_format {
     ($text:expr, $item_type:ty) => {{
-        let formatted = crate::format_text::<$item_type>(&$text).unwrap();
+        let formatted = $crate::format_text::<$item_type>(&$text).unwrap();
         let expected = $text;
         similar_asserts::assert_eq!(formatted, expected);
     }};
 
     ($text:expr, $expected:expr, $item_type:ty) => {{
-        let formatted = crate::format_text::<$item_type>(&$text).unwrap();
+        let formatted = $crate::format_text::<$item_type>(&$text).unwrap();
+        similar_asserts::assert_eq!(formatted, $expected);
+    }};
+
+    ($text:expr, $expected:expr, $item_type:ty, $options:expr) => {{
+        let formatted = $crate::format_text_with_options::<$item_type>(&$text, &$options).unwrap();
         similar_asserts::assert_eq!(formatted, $expected);
     }};
 }
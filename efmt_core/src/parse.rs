@@ -29,6 +29,16 @@ pub enum Error {
         path: Option<Arc<PathBuf>>,
     },
 
+    /// Syntax that is not supported by the target OTP version (see [TokenStream::set_target_otp()]).
+    #[error("Parse failed:{}", Self::unsupported_syntax_message(.position, .feature, .otp, .text, .path))]
+    UnsupportedSyntax {
+        position: Position,
+        feature: &'static str,
+        otp: u32,
+        text: Arc<String>,
+        path: Option<Arc<PathBuf>>,
+    },
+
     /// Error during tokenization.
     #[error("Tokenize failed:{}", Self::tokenize_error_message(.source, .text))]
     TokenizeError {
@@ -54,6 +64,21 @@ impl Error {
         }
     }
 
+    pub(crate) fn unsupported_syntax(
+        ts: &TokenStream,
+        position: Position,
+        feature: &'static str,
+        otp: u32,
+    ) -> Self {
+        Self::UnsupportedSyntax {
+            position,
+            feature,
+            otp,
+            text: ts.text(),
+            path: ts.filepath(),
+        }
+    }
+
     pub(crate) fn tokenize_error(ts: &TokenStream, source: erl_tokenize::Error) -> Self {
         Self::TokenizeError {
             source,
@@ -61,14 +86,30 @@ impl Error {
         }
     }
 
-    pub(crate) fn position(&self) -> Position {
+    /// Returns the position where this error occurred.
+    pub fn position(&self) -> Position {
         match self {
             Self::UnexpectedEof { position, .. } => *position,
             Self::UnexpectedToken { position, .. } => *position,
+            Self::UnsupportedSyntax { position, .. } => *position,
             Self::TokenizeError { source, .. } => source.position().clone().into(),
         }
     }
 
+    /// Returns a short description of this error (without the position and source line).
+    pub fn reason(&self) -> String {
+        match self {
+            Self::UnexpectedEof { .. } => "unexpected EOF".to_owned(),
+            Self::UnexpectedToken { .. } => "unexpected token".to_owned(),
+            Self::UnsupportedSyntax { feature, otp, .. } => format!("{feature} require OTP {otp}+"),
+            Self::TokenizeError { source, .. } => {
+                let message = source.to_string();
+                let end = message.find(" (").unwrap_or(message.len());
+                message[..end].to_owned()
+            }
+        }
+    }
+
     fn tokenize_error_message(source: &erl_tokenize::Error, text: &Arc<String>) -> String {
         let source_message = source.to_string();
         let source_message_end = source_message.find(" (").unwrap_or(source_message.len());
@@ -93,6 +134,21 @@ impl Error {
         )
     }
 
+    fn unsupported_syntax_message(
+        position: &Position,
+        feature: &str,
+        otp: &u32,
+        text: &Arc<String>,
+        path: &Option<Arc<PathBuf>>,
+    ) -> String {
+        crate::error::generate_error_message(
+            text,
+            path.as_ref().map(|x| &**x),
+            *position,
+            &format!("{feature} require OTP {otp}+"),
+        )
+    }
+
     fn unexpected_token_message(
         position: &Position,
         text: &Arc<String>,
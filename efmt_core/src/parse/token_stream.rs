@@ -26,6 +26,9 @@ pub struct TokenStream {
     missing_macros: HashSet<String>,
     known_replacement: HashSet<(usize, Vec<LexicalToken>)>,
     disable_macro_expand: bool,
+    term_mode: bool,
+    target_otp: Option<u32>,
+    unsupported_syntax: Option<Error>,
     parsing_tokens: bool,
     text: Arc<String>,
     path: Option<Arc<PathBuf>>,
@@ -39,7 +42,7 @@ impl TokenStream {
             .next_position()
             .filepath()
             .map(|p| Arc::new(p.to_owned()));
-        Self {
+        let mut this = Self {
             tokenizer,
             tokens: Vec::new(),
             current_token_index: 0,
@@ -50,11 +53,34 @@ impl TokenStream {
             missing_macros: HashSet::new(),
             known_replacement: HashSet::new(),
             disable_macro_expand: false,
+            term_mode: false,
+            target_otp: None,
+            unsupported_syntax: None,
             parsing_tokens: false,
             text,
             path,
             last_parse_error: None,
+        };
+        this.skip_shebang();
+        this
+    }
+
+    // Treats the `#!` line at the beginning of an escript as a comment so that it's preserved verbatim.
+    fn skip_shebang(&mut self) {
+        if !self.text.starts_with("#!") {
+            return;
+        }
+
+        let start_position = Position::from(self.tokenizer.next_position());
+        let line_len = self.text.find(['\r', '\n']).unwrap_or(self.text.len());
+        while self.tokenizer.next_position().offset() < line_len {
+            self.tokenizer.consume_char();
         }
+        let end_position = Position::from(self.tokenizer.next_position());
+        self.comments.insert(
+            start_position,
+            CommentToken::new(false, start_position, end_position),
+        );
     }
 
     pub fn parse_tokens<T: Parse>(&mut self, tokens: Vec<LexicalToken>) -> Result<T> {
@@ -82,7 +108,7 @@ impl TokenStream {
             if self
                 .last_parse_error
                 .as_ref()
-                .map_or(true, |e0| e0.position() < e.position())
+                .is_none_or(|e0| e0.position() < e.position())
             {
                 self.last_parse_error = Some(e.clone());
             }
@@ -91,7 +117,7 @@ impl TokenStream {
             if self
                 .last_parse_error
                 .as_ref()
-                .map_or(false, |e| e.position() < succeeded_position)
+                .is_some_and(|e| e.position() < succeeded_position)
             {
                 self.last_parse_error = None;
             }
@@ -169,6 +195,55 @@ impl TokenStream {
         result
     }
 
+    /// Parses terms of a data file (e.g., `.config`) where opaque terms such as `<0.123.0>` can appear.
+    pub fn with_term_mode_enabled<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let term_mode = std::mem::replace(&mut self.term_mode, true);
+        let result = f(self);
+        self.term_mode = term_mode;
+        result
+    }
+
+    pub fn is_term_mode(&self) -> bool {
+        self.term_mode
+    }
+
+    /// Sets the OTP release (e.g., `25`) that the parsed code must be compatible with.
+    ///
+    /// If set, syntax introduced in a later release is reported by [TokenStream::check_target_otp()].
+    /// By default, all known syntax is accepted.
+    pub fn set_target_otp(&mut self, otp: u32) {
+        self.target_otp = Some(otp);
+    }
+
+    /// Returns an error if the parsed code contains syntax newer than [TokenStream::set_target_otp()].
+    ///
+    /// If there are multiple such items, the first one in the text is reported.
+    pub fn check_target_otp(&mut self) -> Result<()> {
+        if let Some(e) = self.unsupported_syntax.take() {
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // Records a use of `feature` that was introduced in the given OTP release.
+    pub(crate) fn require_otp(&mut self, otp: u32, feature: &'static str, span: &impl Span) {
+        if self.target_otp.is_none_or(|target| otp <= target) {
+            return;
+        }
+
+        let position = span.start_position();
+        if self
+            .unsupported_syntax
+            .as_ref()
+            .is_none_or(|e| position < e.position())
+        {
+            self.unsupported_syntax = Some(Error::unsupported_syntax(self, position, feature, otp));
+        }
+    }
+
     pub fn prev_token_end_position(&self) -> Position {
         if let Some(i) = self.current_token_index.checked_sub(1) {
             self.tokens[i].end_position()
@@ -222,9 +297,10 @@ impl TokenStream {
                     continue;
                 }
                 erl_tokenize::Token::Comment(x) => {
-                    let is_trailing = self.tokens.last().map_or(false, |y| {
-                        y.start_position().line() == x.start_position().line()
-                    });
+                    let is_trailing = self
+                        .tokens
+                        .last()
+                        .is_some_and(|y| y.start_position().line() == x.start_position().line());
                     self.comments.insert(
                         start_position,
                         CommentToken::new(is_trailing, start_position, end_position),
@@ -248,6 +324,7 @@ impl TokenStream {
                     KeywordToken::new(x.value(), start_position, end_position).into()
                 }
                 erl_tokenize::Token::SigilString(_) => {
+                    self.require_otp(27, "sigils", &(start_position..end_position));
                     SigilStringToken::new(start_position, end_position).into()
                 }
                 erl_tokenize::Token::String(x) => {
@@ -261,10 +338,10 @@ impl TokenStream {
             self.current_token_index += 1;
 
             match &token {
-                LexicalToken::Symbol(x) if x.value() == Symbol::Question => {
-                    if !self.disable_macro_expand {
-                        return self.expand_macro_and_read_token();
-                    }
+                LexicalToken::Symbol(x)
+                    if x.value() == Symbol::Question && !self.disable_macro_expand =>
+                {
+                    return self.expand_macro_and_read_token();
                 }
                 LexicalToken::Symbol(x) if x.value() == Symbol::Hyphen => {
                     let index = self.current_token_index;
@@ -451,6 +528,10 @@ impl Iterator for TokenStream {
     }
 }
 
+pub(crate) fn is_predefined_macro(name: &str) -> bool {
+    get_predefined_macro(name, Position::new(0, 0, 0)).is_some()
+}
+
 fn get_predefined_macro(name: &str, position: Position) -> Option<Vec<LexicalToken>> {
     let token: LexicalToken = match name {
         "MODULE" | "FUNCTION_NAME" => dummy_atom(position).into(),
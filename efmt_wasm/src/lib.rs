@@ -16,11 +16,15 @@ pub fn check(text: *const u8, text_len: i32) -> *mut Vec<u8> {
 pub fn format(text: *const u8, text_len: i32) -> *mut Vec<u8> {
     let text = unsafe { std::slice::from_raw_parts(text, text_len as usize) };
     let text = std::str::from_utf8(text).unwrap_or_else(|e| panic!("{e}"));
-    let formatted = efmt_core::format_text::<efmt_core::items::ModuleOrConfig>(text)
-        .unwrap_or_else(|e| panic!("{e}"));
+    let formatted = format_wasm(text).unwrap_or_else(|e| panic!("{e}"));
     Box::into_raw(Box::new(formatted.into_bytes()))
 }
 
+/// Formats an Erlang module (or config) text with the default options.
+pub fn format_wasm(text: &str) -> Result<String, String> {
+    efmt_core::format_text::<efmt_core::items::ModuleOrConfig>(text).map_err(|e| e.to_string())
+}
+
 #[no_mangle]
 pub fn vec_offset(v: *mut Vec<u8>) -> *mut u8 {
     unsafe { &mut *v }.as_mut_ptr()
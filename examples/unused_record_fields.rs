@@ -0,0 +1,139 @@
+//! Reports record fields that are never read nor written in a module.
+//!
+//! ```console
+//! $ cargo run --example unused_record_fields -- src/foo.erl
+//! src/foo.erl:3:22: field `bar` of record `foo` is never used
+//! ```
+//!
+//! Only the record expressions in function bodies and record field defaults are inspected,
+//! i.e., records used only via macros or type specifications are reported as unused.
+use efmt_core::items::expressions::{BaseExpr, FullExpr};
+use efmt_core::items::forms::Form;
+use efmt_core::items::{Either, Expr, Module};
+use efmt_core::span::Span as _;
+use std::collections::HashSet;
+
+fn main() -> anyhow::Result<()> {
+    let mut unused_fields = 0;
+    for path in std::env::args().skip(1) {
+        let module: Module = efmt::parse_file(&path)?;
+
+        let mut used = HashSet::new();
+        for form in module.children() {
+            match form.get() {
+                Form::FunDecl(x) => {
+                    for clause in x.clauses() {
+                        clause
+                            .children()
+                            .for_each(|expr| collect_used_fields(expr, &mut used));
+                    }
+                }
+                Form::RecordDecl(x) => {
+                    x.fields()
+                        .iter()
+                        .filter_map(|field| field.default_value())
+                        .for_each(|expr| collect_used_fields(expr, &mut used));
+                }
+                _ => {}
+            }
+        }
+
+        for form in module.children() {
+            let Form::RecordDecl(x) = form.get() else {
+                continue;
+            };
+            let record_name = x.record_name().value();
+            for field in x.fields() {
+                let field_name = field.field_name();
+                if used.contains(&(record_name.to_owned(), field_name.value().to_owned())) {
+                    continue;
+                }
+                let position = field_name.start_position();
+                println!(
+                    "{path}:{}:{}: field `{}` of record `{record_name}` is never used",
+                    position.line(),
+                    position.column(),
+                    field_name.value()
+                );
+                unused_fields += 1;
+            }
+        }
+    }
+
+    if unused_fields > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn collect_used_fields(expr: &Expr, used: &mut HashSet<(String, String)>) {
+    collect_used_fields_in_full_expr(expr.get(), used);
+}
+
+fn collect_used_fields_in_full_expr(expr: &FullExpr, used: &mut HashSet<(String, String)>) {
+    match expr {
+        FullExpr::Base(x) => collect_used_fields_in_base_expr(x, used),
+        FullExpr::FunctionCall(x) => {
+            if let Some(module) = x.module_expr() {
+                collect_used_fields_in_base_expr(module, used);
+            }
+            collect_used_fields_in_base_expr(x.function_expr(), used);
+            x.args()
+                .iter()
+                .for_each(|expr| collect_used_fields(expr, used));
+        }
+        FullExpr::BinaryOpCall(x) => x
+            .children()
+            .for_each(|expr| collect_used_fields(expr, used)),
+    }
+}
+
+fn collect_used_fields_in_base_expr(expr: &BaseExpr, used: &mut HashSet<(String, String)>) {
+    match expr {
+        BaseExpr::List(x) => x
+            .children()
+            .for_each(|expr| collect_used_fields(expr, used)),
+        BaseExpr::Tuple(x) => x
+            .children()
+            .for_each(|expr| collect_used_fields(expr, used)),
+        BaseExpr::Map(x) => x
+            .children()
+            .for_each(|expr| collect_used_fields(expr, used)),
+        BaseExpr::MapUpdate(x) => x
+            .children()
+            .for_each(|expr| collect_used_fields(expr, used)),
+        BaseExpr::RecordConstructOrIndex(x) => {
+            let record_name = x.record_name().value();
+            for field in x.field_names() {
+                used.insert((record_name.to_owned(), field.value().to_owned()));
+            }
+            x.children()
+                .for_each(|expr| collect_used_fields(expr, used));
+        }
+        BaseExpr::RecordAccessOrUpdate(x) => {
+            let record_name = x.record_name().value();
+            for field in x.field_names() {
+                used.insert((record_name.to_owned(), field.value().to_owned()));
+            }
+            x.children()
+                .for_each(|expr| collect_used_fields(expr, used));
+        }
+        BaseExpr::Bitstring(x) => {
+            for child in x.children() {
+                match child {
+                    Either::A(x) => collect_used_fields_in_base_expr(x, used),
+                    Either::B(x) => collect_used_fields(x, used),
+                }
+            }
+        }
+        BaseExpr::Function(x) => x
+            .children()
+            .for_each(|expr| collect_used_fields(&expr, used)),
+        BaseExpr::Block(x) => x
+            .children()
+            .for_each(|expr| collect_used_fields(&expr, used)),
+        BaseExpr::UnaryOpCall(x) => collect_used_fields_in_base_expr(x.expr(), used),
+        BaseExpr::Parenthesized(x) => collect_used_fields_in_full_expr(x.get(), used),
+        BaseExpr::Literal(_) | BaseExpr::OpaqueTerm(_) => {}
+    }
+}
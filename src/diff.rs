@@ -1,5 +1,5 @@
 use colored::Colorize;
-use similar::{ChangeTag, TextDiff};
+use similar::{ChangeTag, DiffTag, TextDiff};
 use std::path::Path;
 
 pub fn text_color_diff<P: AsRef<Path>>(original: &str, formatted: &str, file: P) {
@@ -31,3 +31,49 @@ pub fn text_diff<P: AsRef<Path>>(original: &str, formatted: &str, file: P) {
             .header(&format!("a/{file}"), &format!("b/{file}"))
     );
 }
+
+/// Returns the (1-origin) line numbers in `original` where each changed region starts.
+pub fn changed_lines(original: &str, formatted: &str) -> Vec<usize> {
+    TextDiff::from_lines(original, formatted)
+        .ops()
+        .iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .map(|op| op.old_range().start + 1)
+        .collect()
+}
+
+/// Numbers of lines and bytes changed by formatting a file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiffStats {
+    pub added_lines: usize,
+    pub removed_lines: usize,
+    pub original_bytes: usize,
+    pub formatted_bytes: usize,
+}
+
+impl DiffStats {
+    pub fn new(original: &str, formatted: &str) -> Self {
+        let mut stats = Self {
+            original_bytes: original.len(),
+            formatted_bytes: formatted.len(),
+            ..Self::default()
+        };
+        for change in TextDiff::from_lines(original, formatted).iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Insert => stats.added_lines += 1,
+                ChangeTag::Delete => stats.removed_lines += 1,
+                ChangeTag::Equal => {}
+            }
+        }
+        stats
+    }
+}
+
+impl std::ops::AddAssign for DiffStats {
+    fn add_assign(&mut self, other: Self) {
+        self.added_lines += other.added_lines;
+        self.removed_lines += other.removed_lines;
+        self.original_bytes += other.original_bytes;
+        self.formatted_bytes += other.formatted_bytes;
+    }
+}
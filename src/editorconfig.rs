@@ -0,0 +1,195 @@
+//! Minimal [EditorConfig](https://editorconfig.org/) support.
+//!
+//! Only the properties that have a counterpart in [Options] are loaded.
+//! `indent_size`, `insert_final_newline` and `trim_trailing_whitespace` are ignored
+//! because the indentation of efmt is fixed, and a formatted text always ends with a newline
+//! and never contains trailing whitespaces.
+use crate::Options;
+use regex::Regex;
+use std::path::Path;
+
+/// Properties of `.editorconfig` files applicable to a file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EditorConfig {
+    /// `max_line_length` (`off` is treated as unset).
+    pub max_line_length: Option<usize>,
+}
+
+impl EditorConfig {
+    /// Loads the properties applicable to `path` from the `.editorconfig` files in its ancestor directories.
+    ///
+    /// The search stops at a file that contains `root = true`,
+    /// and the properties in a nearer file take precedence.
+    /// Note that numeric ranges in section globs (`{N1..N2}`) are not supported.
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = std::path::absolute(path)?;
+        let mut files = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            let config_path = dir.join(".editorconfig");
+            if !config_path.is_file() {
+                continue;
+            }
+            let file = ConfigFile::parse(&std::fs::read_to_string(&config_path)?);
+            let is_root = file.is_root;
+            files.push((dir.to_path_buf(), file));
+            if is_root {
+                break;
+            }
+        }
+
+        let mut config = Self::default();
+        for (dir, file) in files.iter().rev() {
+            let Ok(relative_path) = path.strip_prefix(dir) else {
+                continue;
+            };
+            let relative_path = relative_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            for section in &file.sections {
+                if !section.glob.is_match(&relative_path) {
+                    continue;
+                }
+                for (key, value) in &section.properties {
+                    config.set(key, value);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Applies these properties to `options`.
+    pub fn apply(&self, mut options: Options) -> Options {
+        if let Some(n) = self.max_line_length {
+            options = options.max_columns(n);
+        }
+        options
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        if key == "max_line_length" {
+            self.max_line_length = value.parse().ok();
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ConfigFile {
+    is_root: bool,
+    sections: Vec<Section>,
+}
+
+impl ConfigFile {
+    fn parse(text: &str) -> Self {
+        let mut is_root = false;
+        let mut sections = Vec::<Section>::new();
+        // Properties of a section with an invalid glob are ignored.
+        let mut in_invalid_section = false;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(glob) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                match glob_to_regex(glob) {
+                    Ok(glob) => {
+                        in_invalid_section = false;
+                        sections.push(Section {
+                            glob,
+                            properties: Vec::new(),
+                        });
+                    }
+                    Err(e) => {
+                        in_invalid_section = true;
+                        log::warn!("invalid glob in .editorconfig: {glob:?} ({e})");
+                    }
+                }
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if in_invalid_section {
+                continue;
+            }
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim().to_ascii_lowercase();
+            if let Some(section) = sections.last_mut() {
+                section.properties.push((key, value));
+            } else if key == "root" {
+                is_root = value == "true";
+            }
+        }
+        Self { is_root, sections }
+    }
+}
+
+#[derive(Debug)]
+struct Section {
+    glob: Regex,
+    properties: Vec<(String, String)>,
+}
+
+fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+    // A glob without `/` matches files in any directory.
+    let glob = if let Some(glob) = glob.strip_prefix('/') {
+        glob.to_owned()
+    } else if glob.contains('/') {
+        glob.to_owned()
+    } else {
+        format!("**/{glob}")
+    };
+
+    let mut pattern = "^".to_owned();
+    let mut chars = glob.chars().peekable();
+    let mut brace_depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            '[' => {
+                pattern.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    pattern.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        pattern.push('\\');
+                    }
+                    pattern.push(c);
+                }
+                pattern.push(']');
+            }
+            '{' => {
+                brace_depth += 1;
+                pattern.push_str("(?:");
+            }
+            '}' if brace_depth > 0 => {
+                brace_depth -= 1;
+                pattern.push(')');
+            }
+            ',' if brace_depth > 0 => pattern.push('|'),
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    pattern.push_str(&regex::escape(&c.to_string()));
+                }
+            }
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern)
+}
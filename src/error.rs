@@ -0,0 +1,102 @@
+use efmt_core::span::Position;
+
+/// Errors returned by [Options](crate::Options) and the top-level functions of this crate.
+///
+/// The [Display](std::fmt::Display) of an error is a one-line summary,
+/// and the full message (including the offending source line) is available from
+/// [source()](std::error::Error::source).
+///
+/// There is no variant for formatting failures because formatting a parsed item cannot fail.
+/// [Error::Parse] has no list of expected tokens because the parser doesn't track them
+/// (it backtracks over alternatives), so only a short `reason` is provided.
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to read a file.
+    Io(std::io::Error),
+
+    /// Failed to tokenize a text (e.g., an unterminated string literal).
+    Lex {
+        position: Position,
+        error: efmt_core::parse::Error,
+    },
+
+    /// Failed to parse a text.
+    ///
+    /// `reason` is a short description of the error such as `"unexpected token"` or `"sigils require OTP 27+"`.
+    Parse {
+        position: Position,
+        reason: String,
+        error: efmt_core::parse::Error,
+    },
+}
+
+impl Error {
+    /// Returns the position where this error occurred (`None` for [Error::Io]).
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Self::Io(_) => None,
+            Self::Lex { position, .. } | Self::Parse { position, .. } => Some(*position),
+        }
+    }
+
+    /// Returns a short description of this error (without the position and source line).
+    pub fn reason(&self) -> String {
+        match self {
+            Self::Io(e) => e.to_string(),
+            Self::Lex { error, .. } => error.reason(),
+            Self::Parse { reason, .. } => reason.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(_) => write!(f, "failed to read a file"),
+            Self::Lex { position, .. } => write!(
+                f,
+                "failed to tokenize the text at line {}, column {}",
+                position.line(),
+                position.column()
+            ),
+            Self::Parse {
+                position, reason, ..
+            } => write!(
+                f,
+                "failed to parse the text at line {}, column {}: {reason}",
+                position.line(),
+                position.column()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Lex { error, .. } | Self::Parse { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<efmt_core::parse::Error> for Error {
+    fn from(error: efmt_core::parse::Error) -> Self {
+        let position = error.position();
+        if matches!(error, efmt_core::parse::Error::TokenizeError { .. }) {
+            Self::Lex { position, error }
+        } else {
+            Self::Parse {
+                position,
+                reason: error.reason(),
+                error,
+            }
+        }
+    }
+}
@@ -74,6 +74,36 @@ impl RebarConfigValue {
     }
 }
 
+/// Parses a newline-separated list of file paths.
+///
+/// Surrounding whitespaces are trimmed, and blank lines and lines starting with `#` are ignored.
+pub fn parse_file_list(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Copies `path` to `{path}{suffix}`.
+///
+/// If the backup file already exists, it's kept as-is and `None` is returned unless `force` is `true`.
+pub fn backup_file<P: AsRef<Path>>(
+    path: P,
+    suffix: &str,
+    force: bool,
+) -> anyhow::Result<Option<PathBuf>> {
+    anyhow::ensure!(!suffix.is_empty(), "backup suffix must not be empty");
+    let mut backup_path = path.as_ref().as_os_str().to_owned();
+    backup_path.push(suffix);
+    let backup_path = PathBuf::from(backup_path);
+    if !force && backup_path.exists() {
+        return Ok(None);
+    }
+    std::fs::copy(&path, &backup_path)?;
+    Ok(Some(backup_path))
+}
+
 pub fn collect_default_target_files() -> anyhow::Result<Vec<PathBuf>> {
     let current_dir = std::env::current_dir()?;
     if is_git_repository(&current_dir) {
@@ -141,14 +171,13 @@ where
 }
 
 fn is_format_target(path: &Path) -> bool {
-    path.file_name()
-        .and_then(|n| n.to_str())
-        .map_or(false, |n| {
-            n == "rebar.config"
-                || n.ends_with(".erl")
-                || n.ends_with(".hrl")
-                || n.ends_with(".app.src")
-        })
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+        n == "rebar.config"
+            || n.ends_with(".erl")
+            || n.ends_with(".hrl")
+            || n.ends_with(".app.src")
+            || n.ends_with(".escript")
+    })
 }
 
 fn is_git_repository<P: AsRef<Path>>(dir: P) -> bool {
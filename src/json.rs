@@ -0,0 +1,281 @@
+//! Minimal JSON support used by the `--rpc` and `--config-dump` modes.
+use std::fmt::Write as _;
+
+/// JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Parses a JSON text.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut parser = Parser { text, offset: 0 };
+        let value = parser.parse_value()?;
+        parser.skip_whitespaces();
+        anyhow::ensure!(
+            parser.offset == text.len(),
+            "unexpected trailing characters at offset {}",
+            parser.offset
+        );
+        Ok(value)
+    }
+
+    /// Returns the value associated with `key` if this is an object.
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        if let Self::Object(entries) = self {
+            entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Self::Bool(v) = self {
+            Some(*v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        if let Self::Number(v) = self {
+            if *v >= 0.0 && v.fract() == 0.0 {
+                return Some(*v as usize);
+            }
+        }
+        None
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::String(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(v) => write!(f, "{v}"),
+            Self::Number(v) => write!(f, "{v}"),
+            Self::String(v) => write!(f, "{}", escape_string(v)),
+            Self::Array(vs) => {
+                write!(f, "[")?;
+                for (i, v) in vs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{v}", escape_string(k))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Makes a JSON string literal from the given string.
+pub fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => {
+                write!(escaped, "\\u{:04x}", c as u32).expect("unreachable");
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl Parser<'_> {
+    fn parse_value(&mut self) -> anyhow::Result<JsonValue> {
+        self.skip_whitespaces();
+        match self.peek() {
+            Some('n') => self.parse_literal("null", JsonValue::Null),
+            Some('t') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some('f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some('"') => self.parse_string().map(JsonValue::String),
+            Some('[') => self.parse_array(),
+            Some('{') => self.parse_object(),
+            Some('-' | '0'..='9') => self.parse_number(),
+            Some(c) => anyhow::bail!("unexpected character {c:?} at offset {}", self.offset),
+            None => anyhow::bail!("unexpected end of JSON text"),
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> anyhow::Result<JsonValue> {
+        anyhow::ensure!(
+            self.text[self.offset..].starts_with(literal),
+            "invalid literal at offset {}",
+            self.offset
+        );
+        self.offset += literal.len();
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> anyhow::Result<JsonValue> {
+        let start = self.offset;
+        while let Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9') = self.peek() {
+            self.offset += 1;
+        }
+        let n = self.text[start..self.offset]
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid number at offset {start}"))?;
+        Ok(JsonValue::Number(n))
+    }
+
+    fn parse_string(&mut self) -> anyhow::Result<String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            let c = self.next_char()?;
+            match c {
+                '"' => return Ok(s),
+                '\\' => match self.next_char()? {
+                    '"' => s.push('"'),
+                    '\\' => s.push('\\'),
+                    '/' => s.push('/'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'u' => {
+                        let start = self.offset;
+                        let mut code = self.parse_hex4()?;
+                        if (0xD800..0xDC00).contains(&code) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.parse_hex4()?;
+                            if !(0xDC00..0xE000).contains(&low) {
+                                anyhow::bail!("invalid surrogate pair at offset {start}");
+                            }
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        } else if (0xDC00..0xE000).contains(&code) {
+                            anyhow::bail!("lone low surrogate at offset {start}");
+                        }
+                        s.push(char::from_u32(code).ok_or_else(|| {
+                            anyhow::anyhow!("invalid unicode escape at offset {}", self.offset)
+                        })?);
+                    }
+                    c => anyhow::bail!("invalid escape {c:?} at offset {}", self.offset),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> anyhow::Result<u32> {
+        let hex = self
+            .text
+            .get(self.offset..self.offset + 4)
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of JSON text"))?;
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!("invalid unicode escape at offset {}", self.offset);
+        }
+        let code = u32::from_str_radix(hex, 16).expect("unreachable");
+        self.offset += 4;
+        Ok(code)
+    }
+
+    fn parse_array(&mut self) -> anyhow::Result<JsonValue> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespaces();
+        if self.peek() == Some(']') {
+            self.offset += 1;
+            return Ok(JsonValue::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespaces();
+            match self.next_char()? {
+                ',' => {}
+                ']' => return Ok(JsonValue::Array(values)),
+                c => anyhow::bail!("unexpected character {c:?} at offset {}", self.offset),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> anyhow::Result<JsonValue> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespaces();
+        if self.peek() == Some('}') {
+            self.offset += 1;
+            return Ok(JsonValue::Object(entries));
+        }
+        loop {
+            self.skip_whitespaces();
+            let key = self.parse_string()?;
+            self.skip_whitespaces();
+            self.expect(':')?;
+            entries.push((key, self.parse_value()?));
+            self.skip_whitespaces();
+            match self.next_char()? {
+                ',' => {}
+                '}' => return Ok(JsonValue::Object(entries)),
+                c => anyhow::bail!("unexpected character {c:?} at offset {}", self.offset),
+            }
+        }
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.offset += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    fn next_char(&mut self) -> anyhow::Result<char> {
+        let c = self
+            .peek()
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of JSON text"))?;
+        self.offset += c.len_utf8();
+        Ok(c)
+    }
+
+    fn expect(&mut self, expected: char) -> anyhow::Result<()> {
+        let offset = self.offset;
+        let c = self.next_char()?;
+        anyhow::ensure!(
+            c == expected,
+            "expected {expected:?} but got {c:?} at offset {offset}"
+        );
+        Ok(())
+    }
+}
@@ -1,61 +1,363 @@
-use efmt_core::format::{Format, Formatter};
+use efmt_core::format::{
+    CollectionLayout, Format, FormatOptions, Formatter, LineEnding, OperatorBreak,
+};
+use efmt_core::items::{forms, ExprSnippet, Form, Module, ModuleOrConfig};
 use efmt_core::parse::{Parse, TokenStream};
+use efmt_core::span::Span;
+use std::borrow::Cow;
+use std::ops::{Range, RangeInclusive};
 use std::path::Path;
 
+use self::editorconfig::EditorConfig;
+pub use self::error::Error;
+
 pub mod diff;
+pub mod editorconfig;
+mod error;
 pub mod files;
+pub mod json;
+pub mod normalize;
+pub mod testing;
 
 /// Formats an Erlang file with the default options.
-pub fn format_file<T: Parse + Format, P: AsRef<Path>>(path: P) -> anyhow::Result<String> {
+pub fn format_file<T: Parse + Format, P: AsRef<Path>>(path: P) -> Result<String, Error> {
     Options::new().format_file::<T, P>(path)
 }
 
 /// Formats an Erlang text with the default options.
-pub fn format_text<T: Parse + Format>(text: &str) -> anyhow::Result<String> {
+pub fn format_text<T: Parse + Format>(text: &str) -> Result<String, Error> {
     Options::new().format_text::<T>(text)
 }
 
+/// Formats an Erlang module (or a config file such as `rebar.config`) with the default options.
+///
+/// This is a shorthand for `format_text::<ModuleOrConfig>(text)`.
+pub fn format_str(text: &str) -> Result<String, Error> {
+    format_text::<ModuleOrConfig>(text)
+}
+
+/// Formats an Erlang expression with the default options.
+///
+/// The expression may be followed by a `.` or `,` terminator (e.g., an input of the Erlang shell).
+/// The terminator is kept in the result.
+/// This is a shorthand for `format_text::<ExprSnippet>(text)`.
+pub fn format_expr(text: &str) -> Result<String, Error> {
+    format_text::<ExprSnippet>(text)
+}
+
+/// Parses an Erlang file without formatting it.
+pub fn parse_file<T: Parse, P: AsRef<Path>>(path: P) -> Result<T, Error> {
+    Options::new().parse_file::<T, P>(path)
+}
+
+/// Parses an Erlang text without formatting it.
+pub fn parse_text<T: Parse>(text: &str) -> Result<T, Error> {
+    Options::new().parse_text::<T>(text)
+}
+
+/// Infers a column budget from the width of the widest line of `text`.
+///
+/// The width is rounded up to a multiple of 10 and then clamped into `range`
+/// (if `range` is empty, its end is returned).
+pub fn infer_max_columns(text: &str, range: &RangeInclusive<usize>) -> usize {
+    let widest = text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    widest
+        .div_ceil(10)
+        .saturating_mul(10)
+        .max(*range.start())
+        .min(*range.end())
+}
+
 /// Options to format an item.
 #[derive(Debug, Clone, Default)]
 pub struct Options {
     default_off: bool,
+    target_otp: Option<u32>,
+    auto_max_columns: Option<RangeInclusive<usize>>,
+    format_options: FormatOptions,
 }
 
 impl Options {
+    /// The default range used by [Options::auto_max_columns()] in the command-line interface.
+    pub const DEFAULT_AUTO_MAX_COLUMNS_RANGE: RangeInclusive<usize> = 80..=120;
+
     /// Makes an [Options] instance with the default settings.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Makes an [Options] instance from the `.editorconfig` files applicable to `path` (see [editorconfig]).
+    pub fn from_editorconfig<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(EditorConfig::load(path)?.apply(Self::new()))
+    }
+
     pub fn default_off(mut self) -> Self {
         self.default_off = true;
         self
     }
 
-    pub fn format_file<T: Parse + Format, P: AsRef<Path>>(self, path: P) -> anyhow::Result<String> {
+    /// Rejects syntax introduced after the given OTP release (e.g., `maybe` expressions require OTP 25).
+    ///
+    /// By default, all known syntax is accepted.
+    pub fn target_otp(mut self, otp: u32) -> Self {
+        self.target_otp = Some(otp);
+        self
+    }
+
+    /// Infers the column budget of each text from its widest line (see [infer_max_columns()]).
+    ///
+    /// This overrides [Options::max_columns()].
+    /// An empty range or one starting below [FormatOptions::MIN_MAX_COLUMNS] is ignored with a warning.
+    pub fn auto_max_columns(mut self, range: RangeInclusive<usize>) -> Self {
+        if range.is_empty() || *range.start() < FormatOptions::MIN_MAX_COLUMNS {
+            log::warn!(
+                "ignored the invalid auto max columns range {}..{} (expected MIN..MAX with MIN <= MAX and MIN >= {})",
+                range.start(),
+                range.end(),
+                FormatOptions::MIN_MAX_COLUMNS
+            );
+            return self;
+        }
+        self.auto_max_columns = Some(range);
+        self
+    }
+
+    /// Sets the column budget used when deciding whether an item can be written in a single line.
+    pub fn max_columns(mut self, n: usize) -> Self {
+        self.format_options = self.format_options.max_columns(n);
+        self
+    }
+
+    /// Writes `receive` expressions that only have an `after` clause in a single line if they fit.
+    pub fn compact_receive(mut self, enabled: bool) -> Self {
+        self.format_options = self.format_options.compact_receive(enabled);
+        self
+    }
+
+    /// Writes `try ... of` expressions that have a single `of` clause and at most one `catch` clause
+    /// in a single line if they fit.
+    pub fn compact_try(mut self, enabled: bool) -> Self {
+        self.format_options = self.format_options.compact_try(enabled);
+        self
+    }
+
+    /// Sets where a line break is placed around a binary operator of a multi-line expression.
+    pub fn operator_break(mut self, operator_break: OperatorBreak) -> Self {
+        self.format_options = self.format_options.operator_break(operator_break);
+        self
+    }
+
+    /// Reflows the paragraphs of `@doc` comments so that each line fits in `max_columns`.
+    pub fn wrap_edoc(mut self, max_columns: usize) -> Self {
+        self.format_options = self.format_options.wrap_edoc(max_columns);
+        self
+    }
+
+    /// Sets the number of empty lines around a top-level function definition.
+    pub fn newline_between_functions(mut self, n: usize) -> Self {
+        self.format_options = self.format_options.newline_between_functions(n);
+        self
+    }
+
+    /// Sets the width of a tab stop used to compute the column after a tab in a comment or a string literal.
+    pub fn tab_width(mut self, n: usize) -> Self {
+        self.format_options = self.format_options.tab_width(n);
+        self
+    }
+
+    /// Removes parentheses that never change the evaluation of an expression.
+    pub fn remove_redundant_parens(mut self, enabled: bool) -> Self {
+        self.format_options = self.format_options.remove_redundant_parens(enabled);
+        self
+    }
+
+    /// Sets how a multi-line list, tuple or bitstring is laid out.
+    pub fn collection_layout(mut self, layout: CollectionLayout) -> Self {
+        self.format_options = self.format_options.collection_layout(layout);
+        self
+    }
+
+    /// Aligns the `->` of single-line clauses in the same clause group.
+    pub fn align_clause_arrows(mut self, enabled: bool) -> Self {
+        self.format_options = self.format_options.align_clause_arrows(enabled);
+        self
+    }
+
+    /// Aligns the `=>` and `:=` of the entries of a map written one entry per line.
+    pub fn align_map_values(mut self, enabled: bool) -> Self {
+        self.format_options = self.format_options.align_map_values(enabled);
+        self
+    }
+
+    /// Inserts an empty line between the clauses of a multi-line function definition.
+    pub fn blank_line_between_clauses(mut self, enabled: bool) -> Self {
+        self.format_options = self.format_options.blank_line_between_clauses(enabled);
+        self
+    }
+
+    /// Puts exactly one empty line between the leading attributes of a module and the first function, spec or type.
+    pub fn blank_line_after_attributes(mut self, enabled: bool) -> Self {
+        self.format_options = self.format_options.blank_line_after_attributes(enabled);
+        self
+    }
+
+    /// Keeps primitive elements of a multi-line collection on the same line if they share a line in the original text (default: `true`).
+    pub fn pack_collections(mut self, enabled: bool) -> Self {
+        self.format_options = self.format_options.pack_collections(enabled);
+        self
+    }
+
+    /// Sets the line ending of the formatted text (default: [LineEnding::Lf]).
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.format_options = self.format_options.line_ending(line_ending);
+        self
+    }
+
+    /// Writes a list, tuple or bitstring nested at depth `depth` or deeper one element per line.
+    pub fn force_vertical_depth(mut self, depth: usize) -> Self {
+        self.format_options = self.format_options.force_vertical_depth(depth);
+        self
+    }
+
+    /// Returns `true` if [Options::remove_redundant_parens()] is enabled.
+    pub fn removes_redundant_parens(&self) -> bool {
+        self.format_options.removes_redundant_parens()
+    }
+
+    /// Formats an Erlang file.
+    ///
+    /// The same [Options] instance can be reused to format many files.
+    pub fn format_file<T: Parse + Format, P: AsRef<Path>>(&self, path: P) -> Result<String, Error> {
         let text = std::fs::read_to_string(&path)?;
         let mut tokenizer = erl_tokenize::Tokenizer::new(text);
         tokenizer.set_filepath(path);
         self.format::<T>(tokenizer)
     }
 
-    pub fn format_text<T: Parse + Format>(self, text: &str) -> anyhow::Result<String> {
+    /// Formats an Erlang text.
+    pub fn format_text<T: Parse + Format>(&self, text: &str) -> Result<String, Error> {
         let tokenizer = erl_tokenize::Tokenizer::new(text.to_owned());
         self.format::<T>(tokenizer)
     }
 
+    /// Formats only the function `name/arity` defined in an Erlang text.
+    ///
+    /// Returns the formatted function with the byte range of `text` it replaces
+    /// (i.e., `text[range]` can be replaced with the formatted text), or `None` if the function isn't found.
+    /// The other forms including the `-spec` of the function are left untouched.
+    pub fn format_function(
+        &self,
+        text: &str,
+        name: &str,
+        arity: usize,
+    ) -> Result<Option<(String, Range<usize>)>, Error> {
+        let tokenizer = erl_tokenize::Tokenizer::new(text.to_owned());
+        let format_options = self.format_options_for(text).into_owned();
+        let mut ts = TokenStream::new(tokenizer);
+        let module: Module = self.parse(&mut ts)?;
+        let Some(form) = module
+            .children()
+            .filter(|form| matches!(form.get(), forms::Form::FunDecl(_)))
+            .find(|form| form.func_name_and_arity() == Some((name, arity)))
+        else {
+            return Ok(None);
+        };
+        let formatter = Formatter::with_options(ts, format_options);
+        Ok(Some(formatter.format_item(form)))
+    }
+
+    /// Formats only the top-level forms of an Erlang text for which `keep` returns `true`.
+    ///
+    /// The comments preceding a form (and a trailing comment on its last line) are dropped with the form.
+    /// Note that the forms must still come from a text: building forms from scratch isn't supported.
+    pub fn format_module_subset<F>(&self, text: &str, mut keep: F) -> Result<String, Error>
+    where
+        F: FnMut(&Form) -> bool,
+    {
+        let tokenizer = erl_tokenize::Tokenizer::new(text.to_owned());
+        let mut ts = TokenStream::new(tokenizer);
+        let module: Module = self.parse(&mut ts)?;
+        let forms = module.children().collect::<Vec<_>>();
+
+        // Splits `text` at the end of the line of each form (unless the next form starts on the same line).
+        let boundary = |form: &Form, next: &Form| {
+            let end = form.end_position();
+            if end.line() == next.start_position().line() {
+                return end.offset();
+            }
+            text[end.offset()..]
+                .find('\n')
+                .map_or(text.len(), |i| end.offset() + i + 1)
+        };
+
+        let mut subset = String::new();
+        for (i, form) in forms.iter().enumerate() {
+            if !keep(form) {
+                continue;
+            }
+            let start = if i == 0 {
+                0
+            } else {
+                boundary(forms[i - 1], form)
+            };
+            let end = forms
+                .get(i + 1)
+                .map_or(text.len(), |next| boundary(form, next));
+            subset.push_str(&text[start..end]);
+        }
+        self.format_text::<Module>(&subset)
+    }
+
+    /// Parses an Erlang file without formatting it.
+    pub fn parse_file<T: Parse, P: AsRef<Path>>(&self, path: P) -> Result<T, Error> {
+        let text = std::fs::read_to_string(&path)?;
+        let mut tokenizer = erl_tokenize::Tokenizer::new(text);
+        tokenizer.set_filepath(path);
+        let mut ts = TokenStream::new(tokenizer);
+        self.parse(&mut ts)
+    }
+
+    /// Parses an Erlang text without formatting it.
+    pub fn parse_text<T: Parse>(&self, text: &str) -> Result<T, Error> {
+        let tokenizer = erl_tokenize::Tokenizer::new(text.to_owned());
+        let mut ts = TokenStream::new(tokenizer);
+        self.parse(&mut ts)
+    }
+
+    fn format_options_for(&self, text: &str) -> Cow<'_, FormatOptions> {
+        if let Some(range) = &self.auto_max_columns {
+            let n = infer_max_columns(text, range);
+            Cow::Owned(self.format_options.clone().max_columns(n))
+        } else {
+            Cow::Borrowed(&self.format_options)
+        }
+    }
+
+    fn parse<T: Parse>(&self, ts: &mut TokenStream) -> Result<T, Error> {
+        if let Some(otp) = self.target_otp {
+            ts.set_target_otp(otp);
+        }
+        let item: T = ts.parse()?;
+        ts.check_target_otp()?;
+        Ok(item)
+    }
+
     fn format<T: Parse + Format>(
-        self,
+        &self,
         tokenizer: erl_tokenize::Tokenizer<String>,
-    ) -> anyhow::Result<String> {
+    ) -> Result<String, Error> {
+        let format_options = self.format_options_for(tokenizer.text()).into_owned();
         let mut ts = TokenStream::new(tokenizer);
-        let item: T = ts.parse()?;
-        let mut formatter = Formatter::new(ts);
+        let item: T = self.parse(&mut ts)?;
+        let mut formatter = Formatter::with_options(ts, format_options);
         if self.default_off {
             formatter.skip_formatting();
         }
-        item.format(&mut formatter);
-        let formatted_text = formatter.finish();
+        let formatted_text = formatter.format_all(&item);
         Ok(formatted_text)
     }
 }
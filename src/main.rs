@@ -1,12 +1,20 @@
 use anyhow::Context;
 use clap::{CommandFactory as _, Parser};
+use efmt::diff::DiffStats;
+use efmt::editorconfig::EditorConfig;
 use efmt::files::RebarConfigValue;
+use efmt::json::JsonValue;
+use efmt_core::format::{CollectionLayout, FormatOptions, LineEnding, OperatorBreak};
 use efmt_core::items::ModuleOrConfig;
 use env_logger::Env;
 use rayon::iter::{IntoParallelIterator as _, ParallelIterator};
 use regex::Regex;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
 use std::io::Read as _;
 use std::io::Write as _;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
 
 /// Erlang Code Formatter.
@@ -19,16 +27,102 @@ struct Opt {
     #[clap(long, short)]
     check: bool,
 
+    /// Shows the diff between the input and the formatted text without modifying files.
+    ///
+    /// Unlike `--check`, this exits with 0 even if some files are not formatted correctly.
+    /// `--check --diff` behaves the same as `--check`, i.e., shows the diff and exits with 1 if there are changes.
+    /// Note that neither `--check` nor `--diff` can be combined with `--write`.
+    #[clap(long, conflicts_with = "lint_only")]
+    diff: bool,
+
+    /// Reports the lines that would be reformatted (`{FILE}:{LINE}: would be reformatted`) without showing a diff.
+    ///
+    /// Like `--check`, exits with 1 if any of the input files is not formatted correctly.
+    #[clap(long, conflicts_with = "check")]
+    lint_only: bool,
+
+    /// After the list of files that need to be formatted, prints the number of such files per directory.
+    ///
+    /// Files are grouped by the first two components of their parent directory (e.g., `apps/foo`).
+    /// Only applies when `--check`, `--diff` or `--lint-only` is given.
+    #[clap(long)]
+    group_by_dir: bool,
+
     /// Overwrites input file with the formatted text.
-    #[clap(long, short, conflicts_with = "check")]
+    #[clap(
+        long,
+        short,
+        conflicts_with = "check",
+        conflicts_with = "diff",
+        conflicts_with = "lint_only"
+    )]
     write: bool,
 
+    /// Overwrites the input files and prints `{FILE}: reformatted` or `{FILE}: unchanged` for each of them.
+    ///
+    /// Exits with 1 if any of the files was reformatted.
+    /// This is intended to be used as a pre-commit hook that receives the staged files as arguments.
+    #[clap(
+        long,
+        conflicts_with = "check",
+        conflicts_with = "diff",
+        conflicts_with = "lint_only",
+        conflicts_with = "write"
+    )]
+    pre_commit: bool,
+
+    /// Prints the numbers of added/removed lines and the sizes before/after formatting of each file
+    /// that would be reformatted, followed by their total.
+    ///
+    /// Files are not modified unless `--write` is also given.
+    #[clap(
+        long,
+        conflicts_with = "check",
+        conflicts_with = "diff",
+        conflicts_with = "lint_only",
+        conflicts_with = "pre_commit"
+    )]
+    stats: bool,
+
+    /// Saves the original content of each reformatted file to `{FILE}.orig` before overwriting it.
+    ///
+    /// Unchanged files are not backed up, and existing backup files are kept unless `--force-backup` is given.
+    /// Only applies when `--write` or `--pre-commit` is given.
+    #[clap(long)]
+    backup: bool,
+
+    /// Suffix of the backup files created by `--backup`.
+    ///
+    /// The default value is `.orig`.
+    #[clap(long, value_name = "SUFFIX", value_parser = parse_backup_suffix)]
+    backup_suffix: Option<String>,
+
+    /// Same as `--backup` except that existing backup files are overwritten.
+    #[clap(long)]
+    force_backup: bool,
+
     /// Shows the target input files.
     ///
     /// You can use this flag to exclude some files from the default target, e.g., `$ efmt $(efmt --show-files | grep -v rebar.config)`.
-    #[clap(long, conflicts_with = "check", conflicts_with = "write")]
+    #[clap(
+        long,
+        conflicts_with = "check",
+        conflicts_with = "diff",
+        conflicts_with = "write"
+    )]
     show_files: bool,
 
+    /// Only parses the input files to check their syntax.
+    ///
+    /// Prints nothing if all files are valid. Otherwise, shows the errors and exits with 1.
+    #[clap(
+        long,
+        conflicts_with = "check",
+        conflicts_with = "write",
+        conflicts_with = "show_files"
+    )]
+    parse_only: bool,
+
     /// Excludes files that matches the specified regexs from the default target file list.
     #[clap(short, long = "exclude-file")]
     exclude_files: Vec<regex::Regex>,
@@ -37,14 +131,28 @@ struct Opt {
     #[clap(long)]
     verbose: bool,
 
+    /// Prints only the final tally (`formatted: N, unchanged: M, errors: K`).
+    ///
+    /// All per-file output, such as diffs, formatted texts and lists of files, is suppressed.
+    /// The exit code is the same as without this flag.
+    /// In `--check`, `--diff` and `--lint-only` modes, `formatted` is the number of files that need to be formatted.
+    #[clap(long, conflicts_with = "verbose")]
+    summary_only: bool,
+
     /// Format target files.
     ///
     /// `-` means the standard input.
     /// If no files are specified and any of `-c`, `-w` or `--show-files` options is specified,
-    /// All of the files named `**.{hrl,erl,app.src}` and `**/rebar.config` are used as the default
+    /// All of the files named `**.{hrl,erl,app.src,escript}` and `**/rebar.config` are used as the default
     /// (note that files specified by `.gitignore` will be ignored).
     files: Vec<PathBuf>,
 
+    /// Reads additional target files from the given file (`-` means the standard input).
+    ///
+    /// Each line is a path. Blank lines and lines starting with `#` are ignored.
+    #[clap(long, value_name = "FILE")]
+    files_from: Option<PathBuf>,
+
     /// Executes formatting in parallel.
     #[clap(long)]
     parallel: bool,
@@ -58,6 +166,13 @@ struct Opt {
     #[clap(long)]
     disable_rebar3_mode: bool,
 
+    /// Don't read `.editorconfig` files.
+    ///
+    /// By default, `max_line_length` in the `.editorconfig` files applicable to each target file
+    /// is used as `--max-columns` unless it's specified explicitly (or in `rebar.config`).
+    #[clap(long)]
+    disable_editorconfig: bool,
+
     /// Don't raise an error even if the input contains wrong Erlang code.
     /// `efmt` tries to continue formatting the remaining part of the code as much as possible.
     #[clap(long)]
@@ -66,11 +181,190 @@ struct Opt {
     /// Show colored diff. Only applies when `--check` is given.
     #[clap(long)]
     color: bool,
+
+    /// Column budget used when deciding whether an item can be written in a single line.
+    ///
+    /// Note that efmt never splits a line only because it exceeds this limit.
+    /// If `auto` is specified, the budget of each file is inferred from its widest line
+    /// (rounded up to a multiple of 10 and clamped into `--auto-max-columns-range`).
+    /// The value must be at least 10, and the default value is 100.
+    #[clap(long, value_name = "N|auto")]
+    max_columns: Option<MaxColumns>,
+
+    /// Range into which the value inferred by `--max-columns auto` is clamped.
+    ///
+    /// The default value is `80..120`.
+    #[clap(long, value_name = "MIN..MAX", value_parser = parse_columns_range)]
+    auto_max_columns_range: Option<RangeInclusive<usize>>,
+
+    /// Writes `receive` expressions that only have an `after` clause in a single line if they fit.
+    #[clap(long)]
+    compact_receive: bool,
+
+    /// Writes `try ... of` expressions that have a single `of` clause and at most one `catch` clause
+    /// in a single line if they fit.
+    #[clap(long)]
+    compact_try: bool,
+
+    /// Serves formatting requests over stdin/stdout until EOF.
+    ///
+    /// Each message is a JSON object prefixed by a `Content-Length: {BYTES}\r\n\r\n` header.
+    /// A request is `{"text": "...", "options": {...}}` where `options` (optional) can contain
+    /// `default_off`, `allow_partial_failure`, `max_columns` (a number or `"auto"`), `compact_receive`, `compact_try`,
+    /// `operator_break`, `wrap_edoc`, `newline_between_functions`, `tab_width`, `remove_redundant_parens`,
+    /// `collection_layout`, `align_clause_arrows`, `align_map_values`, `force_vertical_depth`,
+    /// `blank_line_between_clauses`, `blank_line_after_attributes`, `pack_collections`, `newline` and `target_otp`.
+    /// A response is either `{"formatted": "..."}` or `{"error": {"line": N, "column": N, "message": "..."}}`.
+    #[clap(long)]
+    rpc: bool,
+
+    /// Where to break a line around a binary operator of a multi-line expression (`before` or `after`).
+    ///
+    /// The default value is `after`.
+    #[clap(long)]
+    operator_break: Option<OperatorBreak>,
+
+    /// Reflows the paragraphs of `@doc` comments so that each line fits in the given number of columns.
+    ///
+    /// Lines starting with another `@tag`, empty lines, indented lines and code blocks are kept as-is.
+    /// By default, the text of comments is never changed.
+    #[clap(long, value_name = "MAX_COLUMNS")]
+    wrap_edoc: Option<usize>,
+
+    /// Number of empty lines around a top-level function definition.
+    ///
+    /// No empty lines are inserted between a `-spec` and the following function.
+    /// The default value is 2.
+    #[clap(long, value_name = "N")]
+    newline_between_functions: Option<usize>,
+
+    /// Width of a tab stop used to align the items that follow a tab in a comment, a string literal
+    /// or an unformatted region on the same line.
+    ///
+    /// Tabs between tokens are always replaced with spaces.
+    /// The default value is 8.
+    #[clap(long, value_name = "N")]
+    tab_width: Option<usize>,
+
+    /// Removes parentheses around an expression that is a whole element of a list, tuple or argument list,
+    /// or a whole clause body expression.
+    ///
+    /// Parentheses in macro definitions and macro arguments are always kept.
+    #[clap(long)]
+    remove_redundant_parens: bool,
+
+    /// Layout of a multi-line list, tuple or bitstring (`aligned` or `hanging`).
+    ///
+    /// `aligned` aligns the elements with the first one placed next to the opening bracket.
+    /// `hanging` places each element on its own line indented by 4 and the closing bracket on its own line.
+    /// The default value is `aligned`.
+    #[clap(long)]
+    collection_layout: Option<CollectionLayout>,
+
+    /// Pads the patterns of single-line clauses in a `case`, `receive`, `try ... of` or `maybe ... else`
+    /// so that their `->` start at the same column.
+    ///
+    /// Multi-line clauses and clauses that would exceed `--max-columns` once aligned are formatted as usual.
+    #[clap(long)]
+    align_clause_arrows: bool,
+
+    /// Pads the keys of a map written one entry per line so that their `=>` and `:=` start at the same column.
+    ///
+    /// Multi-line entries and entries that would exceed `--max-columns` once aligned are formatted as usual.
+    #[clap(long)]
+    align_map_values: bool,
+
+    /// Writes a list, tuple or bitstring nested at the given depth or deeper one element per line
+    /// even if it fits in a single line.
+    ///
+    /// The outermost collection is at depth 1, and the tag of a tagged tuple stays on the first line.
+    #[clap(long, value_name = "DEPTH")]
+    force_vertical_depth: Option<usize>,
+
+    /// Inserts an empty line between the clauses of a multi-line function definition.
+    ///
+    /// The clauses of `fun`, `case`, `if`, `receive` and `try` are not affected.
+    #[clap(long)]
+    blank_line_between_clauses: bool,
+
+    /// Puts exactly one empty line between the leading attributes of a module (e.g., `-module`, `-export` and `-include`)
+    /// and the first function, spec or type declaration that follows them.
+    ///
+    /// This overrides `--newline-between-functions` for the first function.
+    #[clap(long)]
+    blank_line_after_attributes: bool,
+
+    /// Writes a multi-line list, tuple, bitstring or record one element per line.
+    ///
+    /// By default, primitive elements that share a line in the original text are kept on the same line.
+    #[clap(long)]
+    no_pack_collections: bool,
+
+    /// Line ending of the formatted text (`lf`, `crlf` or `auto`).
+    ///
+    /// `auto` keeps the dominant line ending of each file.
+    /// The default value is `lf`.
+    #[clap(long)]
+    newline: Option<LineEnding>,
+
+    /// Rejects syntax introduced after the given OTP release (e.g., `25`).
+    ///
+    /// For example, `maybe` expressions require OTP 25, map comprehensions require OTP 26 and sigils require OTP 27.
+    /// By default, all known syntax is accepted.
+    #[clap(long, value_name = "VERSION")]
+    target_otp: Option<u32>,
+
+    /// Prints the effective configuration as JSON and exits.
+    ///
+    /// The output reflects the default values, the `efmt` options in rebar.config and the command-line flags.
+    #[clap(long)]
+    config_dump: bool,
 }
 
 impl Opt {
+    fn read_files_from(&mut self) -> anyhow::Result<()> {
+        let Some(path) = &self.files_from else {
+            return Ok(());
+        };
+        let text = if path.to_str() == Some("-") {
+            anyhow::ensure!(
+                !self.files.iter().any(|f| f.to_str() == Some("-")),
+                "`-` cannot be specified as a target file together with `--files-from -`"
+            );
+            let mut text = String::new();
+            std::io::stdin().lock().read_to_string(&mut text)?;
+            text
+        } else {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read the file list {path:?}"))?
+        };
+        self.files.extend(efmt::files::parse_file_list(&text));
+        Ok(())
+    }
+
+    fn backup_if_need(&self, file: &Path) -> anyhow::Result<()> {
+        if !(self.backup || self.force_backup) || file.to_str() == Some("-") {
+            return Ok(());
+        }
+        let suffix = self.backup_suffix.as_deref().unwrap_or(".orig");
+        match efmt::files::backup_file(file, suffix, self.force_backup)
+            .with_context(|| format!("failed to back up {file:?}"))?
+        {
+            Some(backup_path) => log::info!("Backed up {:?} to {:?}", file, backup_path),
+            None => log::info!("Backup of {:?} already exists (skipped)", file),
+        }
+        Ok(())
+    }
+
     fn collect_default_files_if_need(&mut self) -> anyhow::Result<()> {
-        if !self.files.is_empty() || !(self.check || self.write || self.show_files) {
+        if !self.files.is_empty()
+            || !(self.check
+                || self.lint_only
+                || self.write
+                || self.stats
+                || self.show_files
+                || self.parse_only)
+        {
             return Ok(());
         }
 
@@ -101,7 +395,148 @@ impl Opt {
         if self.default_off {
             format_options = format_options.default_off();
         }
+        match self.max_columns {
+            None => {}
+            Some(MaxColumns::Fixed(n)) => {
+                format_options = format_options.max_columns(n);
+            }
+            Some(MaxColumns::Auto) => {
+                format_options = format_options.auto_max_columns(self.auto_max_columns_range());
+            }
+        }
+        if let Some(operator_break) = self.operator_break {
+            format_options = format_options.operator_break(operator_break);
+        }
+        if let Some(n) = self.wrap_edoc {
+            format_options = format_options.wrap_edoc(n);
+        }
+        if let Some(n) = self.newline_between_functions {
+            format_options = format_options.newline_between_functions(n);
+        }
+        if let Some(n) = self.tab_width {
+            format_options = format_options.tab_width(n);
+        }
+        if let Some(layout) = self.collection_layout {
+            format_options = format_options.collection_layout(layout);
+        }
+        if let Some(otp) = self.target_otp {
+            format_options = format_options.target_otp(otp);
+        }
+        if let Some(depth) = self.force_vertical_depth {
+            format_options = format_options.force_vertical_depth(depth);
+        }
+        if let Some(line_ending) = self.newline {
+            format_options = format_options.line_ending(line_ending);
+        }
         format_options
+            .compact_receive(self.compact_receive)
+            .compact_try(self.compact_try)
+            .remove_redundant_parens(self.remove_redundant_parens)
+            .align_clause_arrows(self.align_clause_arrows)
+            .align_map_values(self.align_map_values)
+            .blank_line_between_clauses(self.blank_line_between_clauses)
+            .blank_line_after_attributes(self.blank_line_after_attributes)
+            .pack_collections(!self.no_pack_collections)
+    }
+
+    fn dump_config(&self) -> String {
+        let exclude_files = self
+            .exclude_files
+            .iter()
+            .map(|regex| efmt::json::escape_string(regex.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let entries = [
+            ("default_off", self.default_off.to_string()),
+            ("parallel", self.parallel.to_string()),
+            (
+                "allow_partial_failure",
+                self.allow_partial_failure.to_string(),
+            ),
+            ("disable_rebar3_mode", self.disable_rebar3_mode.to_string()),
+            (
+                "disable_editorconfig",
+                self.disable_editorconfig.to_string(),
+            ),
+            ("color", self.color.to_string()),
+            (
+                "max_columns",
+                match self.max_columns {
+                    None => FormatOptions::DEFAULT_MAX_COLUMNS.to_string(),
+                    Some(MaxColumns::Fixed(n)) => n.to_string(),
+                    Some(MaxColumns::Auto) => efmt::json::escape_string("auto"),
+                },
+            ),
+            (
+                "auto_max_columns_range",
+                efmt::json::escape_string(&format_columns_range(&self.auto_max_columns_range())),
+            ),
+            ("compact_receive", self.compact_receive.to_string()),
+            ("compact_try", self.compact_try.to_string()),
+            (
+                "operator_break",
+                efmt::json::escape_string(&self.operator_break.unwrap_or_default().to_string()),
+            ),
+            (
+                "wrap_edoc",
+                self.wrap_edoc
+                    .map_or_else(|| "null".to_owned(), |n| n.to_string()),
+            ),
+            (
+                "newline_between_functions",
+                self.newline_between_functions
+                    .unwrap_or(FormatOptions::DEFAULT_NEWLINE_BETWEEN_FUNCTIONS)
+                    .to_string(),
+            ),
+            (
+                "tab_width",
+                self.tab_width
+                    .unwrap_or(FormatOptions::DEFAULT_TAB_WIDTH)
+                    .to_string(),
+            ),
+            (
+                "remove_redundant_parens",
+                self.remove_redundant_parens.to_string(),
+            ),
+            (
+                "collection_layout",
+                efmt::json::escape_string(&self.collection_layout.unwrap_or_default().to_string()),
+            ),
+            ("align_clause_arrows", self.align_clause_arrows.to_string()),
+            ("align_map_values", self.align_map_values.to_string()),
+            (
+                "blank_line_between_clauses",
+                self.blank_line_between_clauses.to_string(),
+            ),
+            (
+                "blank_line_after_attributes",
+                self.blank_line_after_attributes.to_string(),
+            ),
+            ("pack_collections", (!self.no_pack_collections).to_string()),
+            (
+                "newline",
+                efmt::json::escape_string(&self.newline.unwrap_or_default().to_string()),
+            ),
+            (
+                "force_vertical_depth",
+                self.force_vertical_depth
+                    .map_or_else(|| "null".to_owned(), |n| n.to_string()),
+            ),
+            (
+                "target_otp",
+                self.target_otp
+                    .map_or_else(|| "null".to_owned(), |n| n.to_string()),
+            ),
+            ("exclude_files", format!("[{exclude_files}]")),
+        ];
+        format!(
+            "{{\n{}\n}}",
+            entries
+                .iter()
+                .map(|(k, v)| format!("  {}: {v}", efmt::json::escape_string(k)))
+                .collect::<Vec<_>>()
+                .join(",\n")
+        )
     }
 
     fn enable_rebar3_mode(&mut self, rebar_config_dir: PathBuf) -> anyhow::Result<()> {
@@ -141,14 +576,140 @@ impl Opt {
                         self.default_off = true;
                         continue;
                     }
+                    "disable_editorconfig" => {
+                        self.disable_editorconfig = true;
+                        continue;
+                    }
                     "allow_partial_failure" => {
                         self.allow_partial_failure = true;
                         continue;
                     }
+                    "compact_receive" => {
+                        self.compact_receive = true;
+                        continue;
+                    }
+                    "compact_try" => {
+                        self.compact_try = true;
+                        continue;
+                    }
+                    "remove_redundant_parens" => {
+                        self.remove_redundant_parens = true;
+                        continue;
+                    }
+                    "align_clause_arrows" => {
+                        self.align_clause_arrows = true;
+                        continue;
+                    }
+                    "align_map_values" => {
+                        self.align_map_values = true;
+                        continue;
+                    }
+                    "blank_line_between_clauses" => {
+                        self.blank_line_between_clauses = true;
+                        continue;
+                    }
+                    "blank_line_after_attributes" => {
+                        self.blank_line_after_attributes = true;
+                        continue;
+                    }
+                    "no_pack_collections" => {
+                        self.no_pack_collections = true;
+                        continue;
+                    }
                     _ => {}
                 }
             } else if let Some((k, v)) = item.as_kv_tuple() {
-                if k == "exclude_file" {
+                if k == "max_columns" {
+                    match v {
+                        RebarConfigValue::Integer(v) => {
+                            self.max_columns
+                                .get_or_insert(MaxColumns::Fixed(*v as usize));
+                            continue;
+                        }
+                        RebarConfigValue::Atom(v) if v == "auto" => {
+                            self.max_columns.get_or_insert(MaxColumns::Auto);
+                            continue;
+                        }
+                        _ => {}
+                    }
+                } else if k == "auto_max_columns_range" {
+                    if let RebarConfigValue::Tuple(v) = v {
+                        if let [RebarConfigValue::Integer(min), RebarConfigValue::Integer(max)] =
+                            v.as_slice()
+                        {
+                            match check_columns_range(*min as usize, *max as usize) {
+                                Ok(range) => {
+                                    self.auto_max_columns_range.get_or_insert(range);
+                                }
+                                Err(e) => {
+                                    log::warn!("{e}");
+                                }
+                            }
+                            continue;
+                        }
+                    }
+                } else if k == "wrap_edoc" {
+                    if let RebarConfigValue::Integer(v) = v {
+                        self.wrap_edoc.get_or_insert(*v as usize);
+                        continue;
+                    }
+                } else if k == "force_vertical_depth" {
+                    if let RebarConfigValue::Integer(v) = v {
+                        self.force_vertical_depth.get_or_insert(*v as usize);
+                        continue;
+                    }
+                } else if k == "target_otp" {
+                    if let RebarConfigValue::Integer(v) = v {
+                        self.target_otp.get_or_insert(*v);
+                        continue;
+                    }
+                } else if k == "newline_between_functions" {
+                    if let RebarConfigValue::Integer(v) = v {
+                        self.newline_between_functions.get_or_insert(*v as usize);
+                        continue;
+                    }
+                } else if k == "tab_width" {
+                    if let RebarConfigValue::Integer(v) = v {
+                        self.tab_width.get_or_insert(*v as usize);
+                        continue;
+                    }
+                } else if k == "operator_break" {
+                    if let RebarConfigValue::Atom(v) = v {
+                        match v.parse() {
+                            Ok(v) => {
+                                self.operator_break.get_or_insert(v);
+                            }
+                            Err(e) => {
+                                log::warn!("{e}");
+                            }
+                        }
+                        continue;
+                    }
+                } else if k == "newline" {
+                    if let RebarConfigValue::Atom(v) = v {
+                        match v.parse() {
+                            Ok(v) => {
+                                self.newline.get_or_insert(v);
+                            }
+                            Err(e) => {
+                                log::warn!("{e}");
+                            }
+                        }
+                        continue;
+                    }
+                } else if k == "collection_layout" {
+                    if let RebarConfigValue::Atom(v) = v {
+                        match v.parse() {
+                            Ok(v) => {
+                                self.collection_layout.get_or_insert(v);
+                            }
+                            Err(e) => {
+                                log::warn!("{e}");
+                            }
+                        }
+                        continue;
+                    }
+                } else if k == "exclude_file" {
                     if let RebarConfigValue::String(v) = v {
                         match Regex::new(v) {
                             Ok(regex) => {
@@ -165,12 +726,102 @@ impl Opt {
             log::warn!("found an unhandled efmt option in rebar.config: {item:?}");
         }
     }
+
+    // Applies the `.editorconfig` files for `file` unless the corresponding options are specified explicitly.
+    fn apply_editorconfig<'a>(
+        &self,
+        format_options: &'a efmt::Options,
+        file: &Path,
+    ) -> anyhow::Result<Cow<'a, efmt::Options>> {
+        if self.disable_editorconfig || self.max_columns.is_some() || file.to_str() == Some("-") {
+            return Ok(Cow::Borrowed(format_options));
+        }
+        let config = EditorConfig::load(file)?;
+        if config == EditorConfig::default() {
+            return Ok(Cow::Borrowed(format_options));
+        }
+        log::debug!("applying .editorconfig to {file:?}: {config:?}");
+        Ok(Cow::Owned(config.apply(format_options.clone())))
+    }
+
+    fn auto_max_columns_range(&self) -> RangeInclusive<usize> {
+        self.auto_max_columns_range
+            .clone()
+            .unwrap_or(efmt::Options::DEFAULT_AUTO_MAX_COLUMNS_RANGE)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MaxColumns {
+    Fixed(usize),
+    Auto,
+}
+
+impl std::str::FromStr for MaxColumns {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            Ok(Self::Auto)
+        } else {
+            let n: usize = s
+                .parse()
+                .map_err(|_| format!("expected a number or `auto`: {s:?}"))?;
+            check_min_max_columns(n)?;
+            Ok(Self::Fixed(n))
+        }
+    }
+}
+
+fn parse_columns_range(s: &str) -> Result<RangeInclusive<usize>, String> {
+    let error = || format!("expected `MIN..MAX`: {s:?}");
+    let (min, max) = s.split_once("..").ok_or_else(error)?;
+    let min: usize = min.parse().map_err(|_| error())?;
+    let max: usize = max.parse().map_err(|_| error())?;
+    check_columns_range(min, max)
+}
+
+fn check_columns_range(min: usize, max: usize) -> Result<RangeInclusive<usize>, String> {
+    if min > max {
+        return Err(format!(
+            "the minimum columns must not exceed the maximum: {min}..{max}"
+        ));
+    }
+    check_min_max_columns(min)?;
+    Ok(min..=max)
+}
+
+fn check_min_max_columns(n: usize) -> Result<(), String> {
+    if n < FormatOptions::MIN_MAX_COLUMNS {
+        return Err(format!(
+            "columns must be at least {}",
+            FormatOptions::MIN_MAX_COLUMNS
+        ));
+    }
+    Ok(())
+}
+
+fn parse_backup_suffix(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        return Err("the backup suffix must not be empty".to_owned());
+    }
+    Ok(s.to_owned())
+}
+
+fn format_columns_range(range: &RangeInclusive<usize>) -> String {
+    format!("{}..{}", range.start(), range.end())
 }
 
 fn main() -> anyhow::Result<()> {
     let mut opt = Opt::parse();
 
-    let loglevel = if opt.verbose { "debug" } else { "info" };
+    let loglevel = if opt.verbose {
+        "debug"
+    } else if opt.summary_only {
+        "warn"
+    } else {
+        "info"
+    };
     env_logger::Builder::from_env(Env::default().default_filter_or(loglevel)).init();
 
     if !opt.disable_rebar3_mode {
@@ -182,7 +833,24 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
-    opt.collect_default_files_if_need()?;
+    if opt.config_dump {
+        println!("{}", opt.dump_config());
+        return Ok(());
+    }
+    if opt.rpc {
+        return serve_rpc(&opt);
+    }
+
+    if opt.files_from.is_some() {
+        opt.read_files_from()?;
+        if opt.files.is_empty() {
+            // e.g., `git diff --name-only | efmt --check --files-from -` without changes.
+            log::info!("No target files were given via `--files-from`.");
+            return Ok(());
+        }
+    } else {
+        opt.collect_default_files_if_need()?;
+    }
     if opt.files.is_empty() {
         Opt::command().print_help()?;
         println!();
@@ -196,8 +864,10 @@ fn main() -> anyhow::Result<()> {
             }
         }
         Ok(())
-    } else if opt.check {
+    } else if opt.check || opt.diff || opt.lint_only {
         check_files(&opt)
+    } else if opt.parse_only {
+        parse_files(&opt)
     } else {
         format_files(&opt)
     }
@@ -209,11 +879,10 @@ fn format_file<P: AsRef<Path>>(
     allow_partial_failure: bool,
 ) -> anyhow::Result<(String, String)> {
     let original = std::fs::read_to_string(&path)?;
-    let opt = format_options.clone();
     let formatted = if allow_partial_failure {
-        opt.format_file::<ModuleOrConfig<true>, _>(path)?
+        format_options.format_file::<ModuleOrConfig<true>, _>(path)?
     } else {
-        opt.format_file::<ModuleOrConfig<false>, _>(path)?
+        format_options.format_file::<ModuleOrConfig<false>, _>(path)?
     };
     Ok((original, formatted))
 }
@@ -224,11 +893,10 @@ fn format_stdin(
 ) -> anyhow::Result<(String, String)> {
     let mut original = String::new();
     std::io::stdin().lock().read_to_string(&mut original)?;
-    let opt = format_options.clone();
     let formatted = if allow_partial_failure {
-        opt.format_text::<ModuleOrConfig<true>>(&original)?
+        format_options.format_text::<ModuleOrConfig<true>>(&original)?
     } else {
-        opt.format_text::<ModuleOrConfig<false>>(&original)?
+        format_options.format_text::<ModuleOrConfig<false>>(&original)?
     };
     Ok((original, formatted))
 }
@@ -243,7 +911,8 @@ fn format_file_or_stdin<P: AsRef<Path>>(
     } else {
         format_file(format_options, &path, allow_partial_failure)
     }?;
-    validate_formatted_text(path, &original, &formatted).context(concat!(
+    let ignore_parens = format_options.removes_redundant_parens();
+    validate_formatted_text(path, &original, &formatted, ignore_parens).context(concat!(
         "Found a token mismatch between the original text ",
         "and the formatted one (maybe efmt bug)"
     ))?;
@@ -253,46 +922,103 @@ fn format_file_or_stdin<P: AsRef<Path>>(
 fn format_files(opt: &Opt) -> anyhow::Result<()> {
     let format_options = opt.to_format_options();
 
-    fn do_format(opt: &Opt, format_options: &efmt::Options, file: &Path) -> anyhow::Result<()> {
-        match format_file_or_stdin(format_options, file, opt.allow_partial_failure) {
+    // Returns `Some(_)` if the formatted text differs from the original one.
+    fn do_format(
+        opt: &Opt,
+        format_options: &efmt::Options,
+        file: &Path,
+    ) -> anyhow::Result<Option<DiffStats>> {
+        match opt
+            .apply_editorconfig(format_options, file)
+            .and_then(|format_options| {
+                format_file_or_stdin(&format_options, file, opt.allow_partial_failure)
+            }) {
             Err(e) => {
-                log::error!("Failed to format {:?}\n{:?}", file, e);
+                if !opt.summary_only {
+                    log::error!("Failed to format {:?}\n{:?}", file, e);
+                }
                 Err(e)
             }
             Ok((original, formatted)) => {
-                if opt.write {
-                    if original != formatted {
-                        let result = overwrite(file, &formatted);
+                let stats = (original != formatted).then(|| DiffStats::new(&original, &formatted));
+                if opt.write || opt.pre_commit {
+                    if stats.is_some() {
+                        let result = opt
+                            .backup_if_need(file)
+                            .and_then(|()| overwrite(file, &formatted));
                         if let Err(e) = &result {
-                            log::error!("Failed to write formatted text to {:?}: {:?}", file, e);
+                            if !opt.summary_only {
+                                log::error!(
+                                    "Failed to write formatted text to {:?}: {:?}",
+                                    file,
+                                    e
+                                );
+                            }
                         } else {
                             log::info!("Overwrote {:?}", file);
                         }
-                        result
-                    } else {
-                        Ok(())
+                        result?;
+                        if opt.pre_commit && !opt.summary_only {
+                            println!("{}: reformatted", file.display());
+                        }
+                    } else if opt.pre_commit && !opt.summary_only {
+                        println!("{}: unchanged", file.display());
                     }
-                } else {
+                } else if !opt.summary_only && !opt.stats {
                     print!("{formatted}");
-                    Ok(())
                 }
+                Ok(stats)
             }
         }
     }
 
-    let error_files = if opt.parallel {
+    let results = if opt.parallel {
         opt.files
             .clone()
             .into_par_iter()
-            .filter(|file| do_format(opt, &format_options, file).is_err())
+            .map(|file| {
+                let result = do_format(opt, &format_options, &file);
+                (file, result)
+            })
             .collect::<Vec<_>>()
     } else {
         opt.files
             .iter()
-            .filter(|file| do_format(opt, &format_options, file).is_err())
-            .cloned()
+            .map(|file| (file.clone(), do_format(opt, &format_options, file)))
             .collect::<Vec<_>>()
     };
+    let error_files = results
+        .iter()
+        .filter(|(_, result)| result.is_err())
+        .map(|(file, _)| file)
+        .collect::<Vec<_>>();
+    let reformatted_files = results
+        .iter()
+        .filter(|(_, result)| matches!(result, Ok(Some(_))))
+        .count();
+
+    if opt.summary_only {
+        print_summary(
+            reformatted_files,
+            results.len() - reformatted_files - error_files.len(),
+            error_files.len(),
+        );
+        if !error_files.is_empty() || (opt.pre_commit && reformatted_files > 0) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if opt.stats {
+        let stats = results
+            .iter()
+            .filter_map(|(file, result)| match result {
+                Ok(Some(stats)) => Some((file, *stats)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        print_stats(&stats, results.len());
+    }
 
     if !error_files.is_empty() {
         if opt.files.len() > 1 {
@@ -308,6 +1034,9 @@ fn format_files(opt: &Opt) -> anyhow::Result<()> {
         } else {
             std::process::exit(1);
         }
+    } else if opt.pre_commit && reformatted_files > 0 {
+        eprintln!();
+        anyhow::bail!("{reformatted_files} file(s) were reformatted");
     } else if opt.write {
         log::info!("All files were formatted correctly!");
     }
@@ -315,55 +1044,160 @@ fn format_files(opt: &Opt) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn check_files(opt: &Opt) -> anyhow::Result<()> {
+fn parse_files(opt: &Opt) -> anyhow::Result<()> {
     let format_options = opt.to_format_options();
 
-    fn do_check(
+    fn do_parse(
         format_options: &efmt::Options,
         file: &Path,
         allow_partial_failure: bool,
-        color: bool,
-    ) -> bool {
-        match format_file_or_stdin(format_options, file, allow_partial_failure) {
+    ) -> anyhow::Result<()> {
+        if file.to_str() == Some("-") {
+            let mut text = String::new();
+            std::io::stdin().lock().read_to_string(&mut text)?;
+            if allow_partial_failure {
+                format_options.parse_text::<ModuleOrConfig<true>>(&text)?;
+            } else {
+                format_options.parse_text::<ModuleOrConfig<false>>(&text)?;
+            }
+        } else if allow_partial_failure {
+            format_options.parse_file::<ModuleOrConfig<true>, _>(file)?;
+        } else {
+            format_options.parse_file::<ModuleOrConfig<false>, _>(file)?;
+        }
+        Ok(())
+    }
+
+    let is_invalid = |file: &PathBuf| {
+        if let Err(e) = do_parse(&format_options, file, opt.allow_partial_failure) {
+            log::error!("Failed to parse {:?}\n{:?}", file, e);
+            true
+        } else {
+            false
+        }
+    };
+    let invalid_files = if opt.parallel {
+        opt.files
+            .clone()
+            .into_par_iter()
+            .filter(is_invalid)
+            .collect::<Vec<_>>()
+    } else {
+        opt.files
+            .iter()
+            .filter(|file| is_invalid(file))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    if !invalid_files.is_empty() {
+        if opt.files.len() > 1 {
+            eprintln!();
+            anyhow::bail!(
+                "Failed to parse the following files:\n{}",
+                invalid_files
+                    .iter()
+                    .map(|f| format!("- {}", f.to_str().unwrap_or("<unknown>")))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        } else {
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn check_files(opt: &Opt) -> anyhow::Result<()> {
+    let format_options = opt.to_format_options();
+
+    // Returns `true` if the file is already formatted correctly.
+    fn do_check(opt: &Opt, format_options: &efmt::Options, file: &Path) -> anyhow::Result<bool> {
+        match opt
+            .apply_editorconfig(format_options, file)
+            .and_then(|format_options| {
+                format_file_or_stdin(&format_options, file, opt.allow_partial_failure)
+            }) {
             Err(e) => {
-                log::error!("Failed to format {:?}\n{:?}", file, e);
-                false
+                if !opt.summary_only {
+                    log::error!("Failed to format {:?}\n{:?}", file, e);
+                }
+                Err(e)
             }
             Ok((original, formatted)) => {
                 if original == formatted {
                     log::info!("{file:?} is already formatted correctly.");
-                    true
+                    Ok(true)
+                } else if opt.summary_only {
+                    Ok(false)
+                } else if opt.lint_only {
+                    let lines = efmt::diff::changed_lines(&original, &formatted);
+                    for line in &lines {
+                        println!("{}:{line}: would be reformatted", file.display());
+                    }
+                    log::info!(
+                        "{file:?} is not formatted correctly (changed regions: {}).",
+                        lines.len()
+                    );
+                    Ok(false)
                 } else {
-                    if color {
+                    if opt.color {
                         efmt::diff::text_color_diff(&original, &formatted, file);
                     } else {
                         efmt::diff::text_diff(&original, &formatted, file);
                     }
                     log::info!("{file:?} is not formatted correctly.");
-                    false
+                    Ok(false)
                 }
             }
         }
     }
 
-    let unformatted_files = if opt.parallel {
+    let results = if opt.parallel {
         opt.files
             .clone()
             .into_par_iter()
-            .filter(|file| !do_check(&format_options, file, opt.allow_partial_failure, opt.color))
+            .map(|file| {
+                let result = do_check(opt, &format_options, &file);
+                (file, result)
+            })
             .collect::<Vec<_>>()
     } else {
         opt.files
             .iter()
-            .filter(|file| !do_check(&format_options, file, opt.allow_partial_failure, opt.color))
-            .cloned()
+            .map(|file| (file.clone(), do_check(opt, &format_options, file)))
             .collect::<Vec<_>>()
     };
+    let unformatted_files = results
+        .iter()
+        .filter(|(_, result)| !matches!(result, Ok(true)))
+        .map(|(file, _)| file.clone())
+        .collect::<Vec<_>>();
 
-    if !unformatted_files.is_empty() {
+    if opt.summary_only {
+        let errors = results.iter().filter(|(_, result)| result.is_err()).count();
+        print_summary(
+            unformatted_files.len() - errors,
+            results.len() - unformatted_files.len(),
+            errors,
+        );
+        if !unformatted_files.is_empty() && (opt.check || opt.lint_only) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let summary = if opt.group_by_dir {
+        format!("\n\nBy directory: {}", group_by_dir(&unformatted_files))
+    } else {
+        String::new()
+    };
+    if unformatted_files.is_empty() {
+        eprintln!("All input files are formatted correctly!");
+    } else if opt.check || opt.lint_only {
         eprintln!();
         anyhow::bail!(
-            "The following files need to be formatted:\n{}",
+            "The following files need to be formatted:\n{}{summary}",
             unformatted_files
                 .iter()
                 .map(|f| format!("- {}", f.to_str().unwrap_or("<unknown>")))
@@ -371,16 +1205,335 @@ fn check_files(opt: &Opt) -> anyhow::Result<()> {
                 .join("\n"),
         );
     } else {
-        eprintln!("All input files are formatted correctly!");
+        // `--diff` only.
+        eprintln!();
+        eprintln!(
+            "{} of the input files need to be formatted.{summary}",
+            unformatted_files.len()
+        );
     }
     Ok(())
 }
 
+fn print_summary(formatted: usize, unchanged: usize, errors: usize) {
+    println!("formatted: {formatted}, unchanged: {unchanged}, errors: {errors}");
+}
+
+fn print_stats(stats: &[(&PathBuf, DiffStats)], total_files: usize) {
+    let mut total = DiffStats::default();
+    for (_, s) in stats {
+        total += *s;
+    }
+    let total_label = format!("total ({} of {total_files} files)", stats.len());
+    let rows = stats
+        .iter()
+        .map(|(file, s)| (file.display().to_string(), s))
+        .chain(std::iter::once((total_label, &total)))
+        .collect::<Vec<_>>();
+
+    let header = ["added", "removed", "before", "after"];
+    let cells = |s: &DiffStats| {
+        [
+            format!("+{}", s.added_lines),
+            format!("-{}", s.removed_lines),
+            s.original_bytes.to_string(),
+            s.formatted_bytes.to_string(),
+        ]
+    };
+    let mut widths = header.map(str::len);
+    for (_, s) in &rows {
+        for (width, cell) in widths.iter_mut().zip(cells(s)) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let print_row = |cells: [&str; 4], file: &str| {
+        for (width, cell) in widths.iter().zip(cells) {
+            print!("{cell:>width$}  ");
+        }
+        println!("{file}");
+    };
+    print_row(header, "file");
+    for (file, s) in &rows {
+        let cells = cells(s);
+        print_row(cells.each_ref().map(String::as_str), file);
+    }
+}
+
+fn group_by_dir(files: &[PathBuf]) -> String {
+    let mut counts = std::collections::BTreeMap::<_, usize>::new();
+    for file in files {
+        let dir = file
+            .parent()
+            .into_iter()
+            .flat_map(|dir| dir.components())
+            .filter(|c| matches!(c, std::path::Component::Normal(_)))
+            .take(2)
+            .collect::<PathBuf>();
+        *counts.entry(dir).or_default() += 1;
+    }
+    counts
+        .iter()
+        .map(|(dir, n)| {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            format!("{}: {n}", dir.display())
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn serve_rpc(opt: &Opt) -> anyhow::Result<()> {
+    let mut reader = std::io::stdin().lock();
+    let mut writer = std::io::stdout().lock();
+    serve_rpc_messages(opt, &mut reader, &mut writer)
+}
+
+// Handles requests until EOF. Only I/O errors end the loop; an invalid request gets an error response.
+fn serve_rpc_messages<R: BufRead, W: std::io::Write>(
+    opt: &Opt,
+    reader: &mut R,
+    writer: &mut W,
+) -> anyhow::Result<()> {
+    while let Some(request) = read_rpc_message(reader)? {
+        let response = match request {
+            Ok(request) => handle_rpc_request(opt, &request),
+            Err(e) => rpc_error_response(e.to_string()),
+        }
+        .to_string();
+        write!(
+            writer,
+            "Content-Length: {}\r\n\r\n{response}",
+            response.len()
+        )?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// The maximum size of an RPC message body.
+const MAX_RPC_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+// Returns `Ok(None)` at EOF, and `Ok(Some(Err(_)))` if the message is invalid but the next one can still be read.
+fn read_rpc_message<R: BufRead>(reader: &mut R) -> std::io::Result<Option<anyhow::Result<String>>> {
+    let mut content_length = None;
+    let mut header_error = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            if content_length.is_some() || header_error.is_some() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "unexpected EOF in a message header",
+                ));
+            }
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if let Some(n) = line.strip_prefix("Content-Length:") {
+            match n.trim().parse::<usize>() {
+                Ok(n) => content_length = Some(n),
+                Err(_) => header_error = Some(anyhow::anyhow!("invalid Content-Length: {n:?}")),
+            }
+        } else if line.is_empty() && (content_length.is_some() || header_error.is_some()) {
+            break;
+        } else if !line.is_empty() {
+            log::warn!("unknown message header: {line:?}");
+        }
+    }
+    if let Some(e) = header_error {
+        // The body can't be skipped without its length.
+        return Ok(Some(Err(e)));
+    }
+
+    let content_length = content_length.expect("unreachable");
+    if content_length > MAX_RPC_MESSAGE_BYTES {
+        std::io::copy(
+            &mut reader.take(content_length as u64),
+            &mut std::io::sink(),
+        )?;
+        return Ok(Some(Err(anyhow::anyhow!(
+            "too large message: {content_length} bytes (max: {MAX_RPC_MESSAGE_BYTES})"
+        ))));
+    }
+    let mut buf = vec![0; content_length];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(String::from_utf8(buf).map_err(anyhow::Error::from)))
+}
+
+fn rpc_error_response(message: String) -> JsonValue {
+    let error = vec![("message".to_owned(), JsonValue::String(message))];
+    JsonValue::Object(vec![("error".to_owned(), JsonValue::Object(error))])
+}
+
+fn handle_rpc_request(opt: &Opt, request: &str) -> JsonValue {
+    match format_rpc_request(opt, request) {
+        Ok(formatted) => {
+            JsonValue::Object(vec![("formatted".to_owned(), JsonValue::String(formatted))])
+        }
+        Err(e) => {
+            let mut error = Vec::new();
+            if let Some((e, position)) = e
+                .downcast_ref::<efmt::Error>()
+                .and_then(|e| Some((e, e.position()?)))
+            {
+                error.push(("line".to_owned(), JsonValue::Number(position.line() as f64)));
+                error.push((
+                    "column".to_owned(),
+                    JsonValue::Number(position.column() as f64),
+                ));
+                error.push(("message".to_owned(), JsonValue::String(e.reason())));
+                JsonValue::Object(vec![("error".to_owned(), JsonValue::Object(error))])
+            } else {
+                rpc_error_response(e.to_string())
+            }
+        }
+    }
+}
+
+fn format_rpc_request(opt: &Opt, request: &str) -> anyhow::Result<String> {
+    let request = JsonValue::parse(request)?;
+    let text = request
+        .get("text")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("missing \"text\" field"))?;
+    let options = request.get("options");
+    let get_bool = |key: &str, default: bool| {
+        options
+            .and_then(|o| o.get(key))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(default)
+    };
+
+    let mut format_options = efmt::Options::new()
+        .compact_receive(get_bool("compact_receive", opt.compact_receive))
+        .compact_try(get_bool("compact_try", opt.compact_try))
+        .remove_redundant_parens(get_bool(
+            "remove_redundant_parens",
+            opt.remove_redundant_parens,
+        ))
+        .align_clause_arrows(get_bool("align_clause_arrows", opt.align_clause_arrows))
+        .align_map_values(get_bool("align_map_values", opt.align_map_values))
+        .blank_line_between_clauses(get_bool(
+            "blank_line_between_clauses",
+            opt.blank_line_between_clauses,
+        ))
+        .blank_line_after_attributes(get_bool(
+            "blank_line_after_attributes",
+            opt.blank_line_after_attributes,
+        ))
+        .pack_collections(get_bool("pack_collections", !opt.no_pack_collections));
+    if get_bool("default_off", opt.default_off) {
+        format_options = format_options.default_off();
+    }
+    if let Some(operator_break) = options
+        .and_then(|o| o.get("operator_break"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.parse().map_err(anyhow::Error::msg))
+        .transpose()?
+        .or(opt.operator_break)
+    {
+        format_options = format_options.operator_break(operator_break);
+    }
+    let max_columns = options.and_then(|o| o.get("max_columns"));
+    let max_columns = if max_columns.and_then(|v| v.as_str()) == Some("auto") {
+        Some(MaxColumns::Auto)
+    } else {
+        max_columns
+            .and_then(|v| v.as_usize())
+            .map(MaxColumns::Fixed)
+            .or(opt.max_columns)
+    };
+    match max_columns {
+        None => {}
+        Some(MaxColumns::Fixed(n)) => {
+            format_options = format_options.max_columns(n);
+        }
+        Some(MaxColumns::Auto) => {
+            format_options = format_options.auto_max_columns(opt.auto_max_columns_range());
+        }
+    }
+    if let Some(n) = options
+        .and_then(|o| o.get("force_vertical_depth"))
+        .and_then(|v| v.as_usize())
+        .or(opt.force_vertical_depth)
+    {
+        format_options = format_options.force_vertical_depth(n);
+    }
+    if let Some(n) = options
+        .and_then(|o| o.get("wrap_edoc"))
+        .and_then(|v| v.as_usize())
+        .or(opt.wrap_edoc)
+    {
+        format_options = format_options.wrap_edoc(n);
+    }
+    if let Some(n) = options
+        .and_then(|o| o.get("newline_between_functions"))
+        .and_then(|v| v.as_usize())
+        .or(opt.newline_between_functions)
+    {
+        format_options = format_options.newline_between_functions(n);
+    }
+    if let Some(n) = options
+        .and_then(|o| o.get("tab_width"))
+        .and_then(|v| v.as_usize())
+        .or(opt.tab_width)
+    {
+        format_options = format_options.tab_width(n);
+    }
+    if let Some(layout) = options
+        .and_then(|o| o.get("collection_layout"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.parse().map_err(anyhow::Error::msg))
+        .transpose()?
+        .or(opt.collection_layout)
+    {
+        format_options = format_options.collection_layout(layout);
+    }
+    if let Some(line_ending) = options
+        .and_then(|o| o.get("newline"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.parse().map_err(anyhow::Error::msg))
+        .transpose()?
+        .or(opt.newline)
+    {
+        format_options = format_options.line_ending(line_ending);
+    }
+
+    if let Some(otp) = options
+        .and_then(|o| o.get("target_otp"))
+        .and_then(|v| v.as_usize())
+        .map(|v| v as u32)
+        .or(opt.target_otp)
+    {
+        format_options = format_options.target_otp(otp);
+    }
+
+    let ignore_parens = format_options.removes_redundant_parens();
+    let formatted = if get_bool("allow_partial_failure", opt.allow_partial_failure) {
+        format_options.format_text::<ModuleOrConfig<true>>(text)?
+    } else {
+        format_options.format_text::<ModuleOrConfig<false>>(text)?
+    };
+    validate_formatted_text("<rpc>", text, &formatted, ignore_parens).context(concat!(
+        "Found a token mismatch between the original text ",
+        "and the formatted one (maybe efmt bug)"
+    ))?;
+    Ok(formatted)
+}
+
+// If `ignore_parens` is `true`, a pair of parentheses in the original text may be missing
+// from the formatted one when `--remove-redundant-parens` considers it redundant.
 fn validate_formatted_text<P: AsRef<Path>>(
     path: P,
     original: &str,
     formatted: &str,
+    ignore_parens: bool,
 ) -> anyhow::Result<()> {
+    use erl_tokenize::values::Symbol;
     use erl_tokenize::{PositionRange as _, Result, Token, Tokenizer};
 
     fn is_visible_token(t: &Result<Token>) -> bool {
@@ -413,9 +1566,112 @@ fn validate_formatted_text<P: AsRef<Path>>(
         }
     }
 
-    let mut tokens0 = Tokenizer::new(original).filter(is_visible_token);
-    let mut tokens1 = Tokenizer::new(formatted).filter(is_visible_token);
+    // Consecutive comments are compared word by word because `--wrap-edoc` can reflow them.
+    fn comment_words<I>(first: &Token, tokens: &mut std::iter::Peekable<I>) -> Vec<String>
+    where
+        I: Iterator<Item = Result<Token>>,
+    {
+        let mut words = Vec::new();
+        let mut token = Some(first.clone());
+        while let Some(Token::Comment(t)) = token {
+            words.extend(
+                t.text()
+                    .trim_start_matches('%')
+                    .split_whitespace()
+                    .map(|w| w.to_owned()),
+            );
+            token = tokens
+                .next_if(|t| matches!(t, Ok(Token::Comment(_))))
+                .and_then(|t| t.ok());
+        }
+        words
+    }
+
+    fn symbol(token: &Token) -> Option<Symbol> {
+        if let Token::Symbol(t) = token {
+            Some(t.value())
+        } else {
+            None
+        }
+    }
+
+    // Returns the offsets of the closing parentheses keyed by those of the opening ones,
+    // for the pairs enclosing a whole item of a list, tuple, argument list or clause body
+    // (see `ParenthesizedExpr::is_redundant()`).
+    fn redundant_parens(text: &str) -> HashMap<usize, usize> {
+        let tokens = Tokenizer::new(text)
+            .filter_map(|t| t.ok())
+            .filter(|t| !matches!(t, Token::Whitespace(_) | Token::Comment(_)))
+            .collect::<Vec<_>>();
+        let is_open = |t: &Token| {
+            matches!(
+                symbol(t),
+                Some(
+                    Symbol::OpenParen
+                        | Symbol::OpenSquare
+                        | Symbol::OpenBrace
+                        | Symbol::Comma
+                        | Symbol::RightArrow
+                )
+            )
+        };
+        let is_close = |t: &Token| {
+            matches!(
+                symbol(t),
+                Some(
+                    Symbol::CloseParen
+                        | Symbol::CloseSquare
+                        | Symbol::CloseBrace
+                        | Symbol::Comma
+                        | Symbol::Semicolon
+                        | Symbol::Dot
+                )
+            ) || matches!(t, Token::Keyword(k) if k.value() == erl_tokenize::values::Keyword::End)
+        };
+
+        let mut parens = HashMap::new();
+        let mut stack = Vec::new();
+        for (i, token) in tokens.iter().enumerate() {
+            match symbol(token) {
+                Some(Symbol::OpenParen) => stack.push(i),
+                Some(Symbol::CloseParen) => {
+                    let Some(j) = stack.pop() else {
+                        continue;
+                    };
+                    if j > 0 && is_open(&tokens[j - 1]) && tokens.get(i + 1).is_some_and(is_close) {
+                        parens.insert(
+                            tokens[j].start_position().offset(),
+                            token.start_position().offset(),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        parens
+    }
+
+    let redundant_parens = if ignore_parens {
+        redundant_parens(original)
+    } else {
+        HashMap::new()
+    };
+    let mut removed_close_parens = HashSet::new();
+    let mut tokens0 = Tokenizer::new(original).filter(is_visible_token).peekable();
+    let mut tokens1 = Tokenizer::new(formatted)
+        .filter(is_visible_token)
+        .peekable();
     while let Some(t0) = tokens0.next().transpose().expect("unreachable") {
+        let offset = t0.start_position().offset();
+        if let Some(&close) = redundant_parens.get(&offset) {
+            if !matches!(tokens1.peek(), Some(Ok(t1)) if symbol(t1) == Some(Symbol::OpenParen)) {
+                removed_close_parens.insert(close);
+                continue;
+            }
+        }
+        if removed_close_parens.remove(&offset) {
+            continue;
+        }
         let t1 = match tokens1.next() {
             Some(Ok(t1)) => t1,
             Some(Err(e)) => {
@@ -435,6 +1691,12 @@ fn validate_formatted_text<P: AsRef<Path>>(
                 return check_extra_token(path, original, Some(Ok(t0)));
             }
         };
+        if text(&t0) != text(&t1)
+            && matches!((&t0, &t1), (Token::Comment(_), Token::Comment(_)))
+            && comment_words(&t0, &mut tokens0) == comment_words(&t1, &mut tokens1)
+        {
+            continue;
+        }
         anyhow::ensure!(
             text(&t0) == text(&t1),
             "{}\n{}",
@@ -465,3 +1727,83 @@ fn overwrite<P: AsRef<Path>>(path: P, text: &str) -> anyhow::Result<()> {
     temp.persist(path)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(body: &[u8]) -> Vec<u8> {
+        let mut message = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+        message.extend_from_slice(body);
+        message
+    }
+
+    #[test]
+    fn serve_rpc_messages_works() {
+        let opt = Opt::parse_from(["efmt"]);
+        let mut input = Vec::new();
+        input.extend(message(br#"{"text":"foo() -> bar."}"#));
+        input.extend(message(b"\xff\xfe"));
+        input.extend(message(br#"{"text":"baz()->qux."}"#));
+        input.extend(b"Content-Length: 1x\r\n\r\n");
+
+        let mut reader = std::io::Cursor::new(input);
+        let mut output = Vec::new();
+        serve_rpc_messages(&opt, &mut reader, &mut output).expect("I/O error");
+
+        let mut output = std::io::Cursor::new(output);
+        let mut responses = Vec::new();
+        while let Some(response) = read_rpc_message(&mut output).expect("I/O error") {
+            let response = response.expect("invalid response");
+            responses.push(JsonValue::parse(&response).expect("invalid JSON"));
+        }
+        assert_eq!(responses.len(), 4);
+        assert_eq!(
+            responses[0].get("formatted").and_then(|v| v.as_str()),
+            Some("foo() -> bar.\n")
+        );
+        assert!(responses[1].get("error").is_some());
+        assert_eq!(
+            responses[2].get("formatted").and_then(|v| v.as_str()),
+            Some("baz() -> qux.\n")
+        );
+        assert!(responses[3].get("error").is_some());
+    }
+
+    #[test]
+    fn read_rpc_message_skips_too_large_body() {
+        let mut input =
+            format!("Content-Length: {}\r\n\r\n", MAX_RPC_MESSAGE_BYTES + 1).into_bytes();
+        input.resize(input.len() + MAX_RPC_MESSAGE_BYTES + 1, b' ');
+        input.extend(message(b"{}"));
+
+        let mut reader = std::io::Cursor::new(input);
+        let first = read_rpc_message(&mut reader)
+            .expect("I/O error")
+            .expect("EOF");
+        assert!(first.is_err());
+        let second = read_rpc_message(&mut reader)
+            .expect("I/O error")
+            .expect("EOF");
+        assert_eq!(second.expect("invalid message"), "{}");
+        assert!(read_rpc_message(&mut reader).expect("I/O error").is_none());
+    }
+
+    #[test]
+    fn validate_formatted_text_works() {
+        let original = "foo(A, B, C) -> f((A), [(B)]), (A + B) * C.\n";
+
+        // Redundant parentheses can be removed.
+        let formatted = "foo(A, B, C) -> f(A, [B]), (A + B) * C.\n";
+        assert!(validate_formatted_text("test", original, formatted, true).is_ok());
+        assert!(validate_formatted_text("test", original, formatted, false).is_err());
+
+        // Parentheses that change the meaning of the expression cannot be removed.
+        let formatted = "foo(A, B, C) -> f((A), [(B)]), A + B * C.\n";
+        assert!(validate_formatted_text("test", original, formatted, true).is_err());
+
+        // Only one of a pair of parentheses cannot be removed.
+        let formatted = "foo(A, B, C) -> f(A), [(B)]), (A + B) * C.\n";
+        assert!(validate_formatted_text("test", original, formatted, true).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! A lightweight alternative to the formatter that only normalizes the whitespace between tokens.
+//!
+//! Unlike [`crate::format_text()`], this pass doesn't parse the input,
+//! so it also accepts texts that aren't complete Erlang modules (e.g., the output of code generators).
+//! Line breaks and indentation are kept as-is.
+use erl_tokenize::{Token, Tokenizer};
+
+/// Symbols that always have a space on both sides.
+const SPACED_SYMBOLS: &[&str] = &[
+    "=", "->", "==", "=:=", "=/=", "/=", "=<", ">=", "||", "<-", "<=", "++", "--", "!", ":=", "=>",
+    "::",
+];
+
+/// Symbols that never have a space before them.
+const NO_SPACE_BEFORE_SYMBOLS: &[&str] = &[",", ".", ";", ")", "]", "}", ":"];
+
+/// Symbols that never have a space after them.
+const NO_SPACE_AFTER_SYMBOLS: &[&str] = &["(", "[", "{", "#", ":", "?"];
+
+/// Normalizes the whitespace between the tokens in the given text.
+///
+/// - Spaces between tokens on the same line are collapsed into a single space (or removed where unnecessary)
+/// - Unambiguous binary operators such as `=`, `->` and `=:=` get a space on both sides
+/// - Trailing whitespace is removed
+pub fn normalize_text(text: &str) -> anyhow::Result<String> {
+    let mut buf = String::new();
+    let mut prev: Option<Token> = None;
+    let mut blank = String::new();
+    for token in Tokenizer::new(text) {
+        let token = token?;
+        if let Token::Whitespace(t) = &token {
+            blank.push_str(t.text());
+            continue;
+        }
+
+        if let Some(i) = blank.rfind('\n') {
+            while buf.ends_with([' ', '\t']) {
+                buf.pop();
+            }
+            buf.push_str(&"\n".repeat(blank.matches('\n').count()));
+            buf.push_str(&blank[i + 1..]);
+        } else if matches!(token, Token::Comment(_)) {
+            buf.push_str(&blank);
+        } else if let Some(prev) = &prev {
+            if needs_space(prev, &token, !blank.is_empty()) {
+                buf.push(' ');
+            }
+        } else {
+            buf.push_str(&blank);
+        }
+        blank.clear();
+
+        buf.push_str(token.text());
+        prev = Some(token);
+    }
+    if blank.contains('\n') {
+        buf.push('\n');
+    }
+    Ok(buf)
+}
+
+fn needs_space(prev: &Token, next: &Token, had_space: bool) -> bool {
+    let is_symbol =
+        |t: &Token, symbols: &[&str]| matches!(t, Token::Symbol(_)) && symbols.contains(&t.text());
+    if is_symbol(prev, NO_SPACE_AFTER_SYMBOLS) || is_symbol(next, NO_SPACE_BEFORE_SYMBOLS) {
+        false
+    } else if is_symbol(prev, SPACED_SYMBOLS)
+        || is_symbol(next, SPACED_SYMBOLS)
+        || is_symbol(prev, &[","])
+    {
+        true
+    } else {
+        had_space
+    }
+}
@@ -0,0 +1,113 @@
+//! Utilities to check the formatter against a corpus of real Erlang files.
+use efmt_core::items::ModuleOrConfig;
+use std::path::{Path, PathBuf};
+
+/// The name of the optional manifest file in a corpus directory.
+///
+/// Each non-empty line of the manifest is a path (relative to the corpus directory) of a file
+/// that is expected to be already formatted (i.e., formatting it must not change its content).
+/// Lines starting with `#` are ignored.
+pub const MANIFEST_FILE_NAME: &str = "efmt-known-good.txt";
+
+/// Result of [check_corpus()].
+#[derive(Debug, Default)]
+pub struct CorpusReport {
+    /// Number of the checked files.
+    pub checked_files: usize,
+
+    /// Files that failed the check.
+    pub failures: Vec<CorpusFailure>,
+}
+
+impl CorpusReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A file that failed the check in [check_corpus()].
+#[derive(Debug)]
+pub struct CorpusFailure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Formats all of `**.{erl,hrl}` files under `dir` twice and checks that
+///
+/// - formatting doesn't fail nor panic,
+/// - the second formatting doesn't change the result of the first one (idempotency), and
+/// - the files listed in the manifest ([MANIFEST_FILE_NAME]) are already formatted.
+///
+/// Note that this function doesn't modify the files.
+pub fn check_corpus<P: AsRef<Path>>(dir: P) -> anyhow::Result<CorpusReport> {
+    let dir = dir.as_ref();
+    let known_good = load_manifest(dir)?;
+
+    let mut report = CorpusReport::default();
+    let mut stack = vec![dir.to_path_buf()];
+    let mut files = Vec::new();
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == "erl" || ext == "hrl")
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    for path in files {
+        report.checked_files += 1;
+        let is_known_good = path
+            .strip_prefix(dir)
+            .is_ok_and(|path| known_good.iter().any(|x| x == path));
+        if let Err(reason) = check_file(&path, is_known_good) {
+            report.failures.push(CorpusFailure { path, reason });
+        }
+    }
+    for path in known_good {
+        if !dir.join(&path).exists() {
+            report.failures.push(CorpusFailure {
+                reason: format!("listed in {MANIFEST_FILE_NAME} but not found"),
+                path: dir.join(path),
+            });
+        }
+    }
+    Ok(report)
+}
+
+fn load_manifest(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let path = dir.join(MANIFEST_FILE_NAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect())
+}
+
+fn check_file(path: &Path, is_known_good: bool) -> Result<(), String> {
+    let original = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let format = |text: &str| {
+        std::panic::catch_unwind(|| crate::format_text::<ModuleOrConfig>(text))
+            .map_err(|_| "formatter panicked".to_owned())?
+            .map_err(|e| e.to_string())
+    };
+
+    let formatted = format(&original)?;
+    if is_known_good && formatted != original {
+        return Err("known-good file was changed by formatting".to_owned());
+    }
+    if format(&formatted)? != formatted {
+        return Err("formatting is not idempotent".to_owned());
+    }
+    Ok(())
+}
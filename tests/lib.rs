@@ -2,18 +2,415 @@ use efmt_core::items::ModuleOrConfig;
 
 #[test]
 fn format_works() -> anyhow::Result<()> {
+    let options = efmt::Options::new();
     for entry in std::fs::read_dir("tests/testdata/")? {
         let entry = entry?;
         let path = entry.path();
         if path
             .extension()
-            .map_or(true, |ext| ext != "erl" && ext != "src")
+            .is_none_or(|ext| ext != "erl" && ext != "src")
         {
             continue;
         }
-        let formatted = efmt::Options::new().format_file::<ModuleOrConfig, _>(&path)?;
+        let formatted = options.format_file::<ModuleOrConfig, _>(&path)?;
         let expected = std::fs::read_to_string(&path)?;
         similar_asserts::assert_eq!(formatted, expected, "target={:?}", path);
     }
     Ok(())
 }
+
+#[test]
+fn format_str_and_format_expr_work() -> anyhow::Result<()> {
+    let formatted = efmt::format_str("-module(foo).\nfoo()->bar.\n")?;
+    similar_asserts::assert_eq!(formatted, "-module(foo).\n\n\nfoo() -> bar.\n");
+
+    let formatted = efmt::format_expr("{A,B}=foo(1,2)")?;
+    similar_asserts::assert_eq!(formatted, "{A, B} = foo(1, 2)");
+    Ok(())
+}
+
+#[test]
+fn format_expr_accepts_optional_terminator() -> anyhow::Result<()> {
+    similar_asserts::assert_eq!(efmt::format_expr("foo(1,2)")?, "foo(1, 2)");
+    similar_asserts::assert_eq!(efmt::format_expr("foo(1,2).")?, "foo(1, 2).");
+    similar_asserts::assert_eq!(efmt::format_expr("X=foo(1,2),")?, "X = foo(1, 2),");
+    assert!(efmt::format_expr("foo(1,2). bar()").is_err());
+    Ok(())
+}
+
+#[test]
+fn format_function_works() -> anyhow::Result<()> {
+    let text = concat!(
+        "-module(foo).\n",
+        "foo(A)->A.\n",
+        "% bar/1\n",
+        "bar(A)->\n",
+        "  [A,\n",
+        "  A]. % trailing\n",
+        "bar(A,B)->{A,B}.\n",
+    );
+    let options = efmt::Options::new();
+
+    let (formatted, range) = options.format_function(text, "bar", 1)?.unwrap();
+    similar_asserts::assert_eq!(formatted, "bar(A) ->\n    [A,\n     A].  % trailing");
+    similar_asserts::assert_eq!(&text[range.clone()], "bar(A)->\n  [A,\n  A]. % trailing");
+
+    let (formatted, _) = options.format_function(text, "bar", 2)?.unwrap();
+    similar_asserts::assert_eq!(formatted, "bar(A, B) -> {A, B}.");
+
+    assert!(options.format_function(text, "baz", 0)?.is_none());
+    assert!(options.format_function(text, "foo", 2)?.is_none());
+    Ok(())
+}
+
+#[test]
+fn auto_max_columns_works() -> anyhow::Result<()> {
+    let range = efmt::Options::DEFAULT_AUTO_MAX_COLUMNS_RANGE;
+    let line = |n| format!("{}\n", "x".repeat(n));
+    assert_eq!(efmt::infer_max_columns(&line(97), &range), 100);
+    assert_eq!(efmt::infer_max_columns(&line(100), &range), 100);
+    assert_eq!(efmt::infer_max_columns(&line(101), &range), 110);
+    assert_eq!(efmt::infer_max_columns(&line(10), &range), 80);
+    assert_eq!(efmt::infer_max_columns(&line(200), &range), 120);
+
+    // Clause arrows are only aligned if the aligned heads fit in the inferred budget
+    // (the widest line is 54 columns, so it is rounded up to 60 and then clamped into the range).
+    let text = concat!(
+        "foo(X) ->\n",
+        "    case X of\n",
+        "        {aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa, B} -> B;\n",
+        "        _ -> X\n",
+        "    end.\n",
+    );
+    let expected = concat!(
+        "foo(X) ->\n",
+        "    case X of\n",
+        "        {aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa, B} -> B;\n",
+        "        _                                        -> X\n",
+        "    end.\n",
+    );
+    let format = |range| {
+        efmt::Options::new()
+            .align_clause_arrows(true)
+            .auto_max_columns(range)
+            .format_text::<ModuleOrConfig>(text)
+    };
+    similar_asserts::assert_eq!(format(40..=80)?, expected);
+    similar_asserts::assert_eq!(format(40..=50)?, text);
+
+    // Invalid ranges are ignored (falling back to the default `max_columns`) instead of causing a panic.
+    #[allow(clippy::reversed_empty_ranges)]
+    let inverted = 120..=80;
+    assert_eq!(efmt::infer_max_columns(&line(100), &inverted), 80);
+    similar_asserts::assert_eq!(format(inverted)?, expected);
+    similar_asserts::assert_eq!(format(5..=80)?, expected);
+    Ok(())
+}
+
+#[test]
+fn editorconfig_works() -> anyhow::Result<()> {
+    use efmt::editorconfig::EditorConfig;
+
+    let dir = tempfile::tempdir()?;
+    let sub_dir = dir.path().join("src/sub");
+    std::fs::create_dir_all(&sub_dir)?;
+    std::fs::write(
+        dir.path().join(".editorconfig"),
+        concat!(
+            "# top-most\n",
+            "root = true\n",
+            "\n",
+            "[*]\n",
+            "indent_size = 2\n",
+            "\n",
+            "[*.{erl,hrl}]\n",
+            "max_line_length = 80\n",
+            "\n",
+            "[/src/**.hrl]\n",
+            "max_line_length = off\n",
+        ),
+    )?;
+    std::fs::write(
+        sub_dir.join(".editorconfig"),
+        "[foo.erl]\nMax_Line_Length = 120\n",
+    )?;
+
+    let load = |path: &str| EditorConfig::load(dir.path().join(path));
+    assert_eq!(load("foo.erl")?.max_line_length, Some(80));
+    assert_eq!(load("src/bar.hrl")?.max_line_length, None);
+    assert_eq!(load("include/bar.hrl")?.max_line_length, Some(80));
+    assert_eq!(load("src/sub/foo.erl")?.max_line_length, Some(120));
+    assert_eq!(load("src/sub/bar.erl")?.max_line_length, Some(80));
+    assert_eq!(load("rebar.config")?, EditorConfig::default());
+
+    // Properties in a section with an invalid glob are ignored.
+    let invalid_dir = dir.path().join("invalid");
+    std::fs::create_dir_all(&invalid_dir)?;
+    std::fs::write(
+        invalid_dir.join(".editorconfig"),
+        "[*.erl]\nmax_line_length=90\n[{bad]\nmax_line_length=200\n",
+    )?;
+    assert_eq!(load("invalid/foo.erl")?.max_line_length, Some(90));
+
+    // `max_line_length` is used as `max_columns`.
+    let text = concat!(
+        "foo(X) ->\n",
+        "    case X of\n",
+        "        {aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa, B} -> B;\n",
+        "        _ -> X\n",
+        "    end.\n",
+    );
+    let format = |path: &str| -> anyhow::Result<String> {
+        Ok(efmt::Options::from_editorconfig(dir.path().join(path))?
+            .align_clause_arrows(true)
+            .format_text::<ModuleOrConfig>(text)?)
+    };
+    similar_asserts::assert_eq!(format("foo.erl")?, text);
+    assert_ne!(format("src/sub/foo.erl")?, text);
+    Ok(())
+}
+
+#[test]
+fn format_module_subset_works() -> anyhow::Result<()> {
+    use efmt_core::items::forms;
+
+    let text = concat!(
+        "%% header\n",
+        "-module(foo).\n",
+        "%% exports\n",
+        "-export([foo/0]).  % trailing\n",
+        "%% foo/0\n",
+        "foo()->bar().\n",
+        "bar()->{ok,1}.\n",
+    );
+    let formatted = efmt::Options::new()
+        .format_module_subset(text, |form| !matches!(form.get(), forms::Form::Export(_)))?;
+    let expected = concat!(
+        "%% header\n",
+        "-module(foo).\n",
+        "%% foo/0\n",
+        "\n",
+        "\n",
+        "foo() -> bar().\n",
+        "\n",
+        "\n",
+        "bar() -> {ok, 1}.\n",
+    );
+    similar_asserts::assert_eq!(formatted, expected);
+    Ok(())
+}
+
+#[test]
+fn target_otp_works() -> anyhow::Result<()> {
+    let text = concat!(
+        "foo(M) ->\n",
+        "    X = #{ K => V || K := V <- M },\n",
+        "    maybe\n",
+        "        {ok, Y} ?= X\n",
+        "    end.\n",
+    );
+    let format = |otp| {
+        efmt::Options::new()
+            .target_otp(otp)
+            .format_text::<ModuleOrConfig>(text)
+    };
+
+    similar_asserts::assert_eq!(format(26)?, text);
+    similar_asserts::assert_eq!(efmt::format_str(text)?, text);
+
+    let error = format(25).unwrap_err();
+    similar_asserts::assert_eq!(error.reason(), "map comprehensions require OTP 26+");
+    similar_asserts::assert_eq!(error.position().map(|p| p.line()), Some(2));
+
+    let error = format(24).unwrap_err();
+    similar_asserts::assert_eq!(error.reason(), "map comprehensions require OTP 26+");
+
+    let error = efmt::Options::new()
+        .target_otp(26)
+        .parse_text::<ModuleOrConfig>("foo() -> ~\"abc\".")
+        .unwrap_err();
+    similar_asserts::assert_eq!(error.reason(), "sigils require OTP 27+");
+    Ok(())
+}
+
+#[test]
+fn error_kinds_work() {
+    let error =
+        efmt::format_file::<ModuleOrConfig, _>("tests/testdata/no_such_file.erl").unwrap_err();
+    assert!(matches!(error, efmt::Error::Io(_)));
+    assert!(error.position().is_none());
+
+    let error = efmt::format_str("foo() -> \"abc.\n").unwrap_err();
+    assert!(matches!(error, efmt::Error::Lex { .. }));
+    similar_asserts::assert_eq!(error.position().map(|p| p.line()), Some(1));
+
+    let error = efmt::format_str("foo() -> bar(.\n").unwrap_err();
+    let efmt::Error::Parse {
+        position, reason, ..
+    } = &error
+    else {
+        panic!("{error:?}");
+    };
+    similar_asserts::assert_eq!((position.line(), position.column()), (1, 14));
+    similar_asserts::assert_eq!(reason, "unexpected token");
+    similar_asserts::assert_eq!(
+        error.to_string(),
+        "failed to parse the text at line 1, column 14: unexpected token"
+    );
+    let source = std::error::Error::source(&error).expect("no source");
+    assert!(source.to_string().starts_with("Parse failed:"));
+}
+
+#[test]
+fn normalize_text_works() -> anyhow::Result<()> {
+    let text = concat!(
+        "foo( A ,B )->\n",
+        "    X=A+B ,   % comment  \n",
+        "    case X==0 of\n",
+        "        true->[1,2 | T];\n",
+        "        false  ->  #{a=>1}\n",
+        "    end.   \n",
+        "bar(\n", // incomplete
+    );
+    let expected = concat!(
+        "foo(A, B) ->\n",
+        "    X = A+B,   % comment\n",
+        "    case X == 0 of\n",
+        "        true -> [1, 2 | T];\n",
+        "        false -> #{a => 1}\n",
+        "    end.\n",
+        "bar(\n",
+    );
+    similar_asserts::assert_eq!(efmt::normalize::normalize_text(text)?, expected);
+    Ok(())
+}
+
+#[test]
+fn json_works() -> anyhow::Result<()> {
+    use efmt::json::JsonValue;
+
+    let value = JsonValue::parse(
+        r#" {"text": "a\"b\né😀", "options": {"max_columns": 80, "default_off": true}, "xs": [null, -1.5, []]} "#,
+    )?;
+    assert_eq!(
+        value.get("text").and_then(|v| v.as_str()),
+        Some("a\"b\né😀")
+    );
+    let options = value.get("options");
+    assert_eq!(
+        options
+            .and_then(|o| o.get("max_columns"))
+            .and_then(|v| v.as_usize()),
+        Some(80)
+    );
+    assert_eq!(
+        options
+            .and_then(|o| o.get("default_off"))
+            .and_then(|v| v.as_bool()),
+        Some(true)
+    );
+    assert_eq!(JsonValue::parse(&value.to_string())?, value);
+
+    assert!(JsonValue::parse(r#"{"text": "foo""#).is_err());
+    assert!(JsonValue::parse("[1, 2] 3").is_err());
+
+    // Surrogate pairs.
+    assert_eq!(
+        JsonValue::parse(r#""\ud83d\ude00""#)?,
+        JsonValue::String("😀".to_owned())
+    );
+    assert!(JsonValue::parse(r#""\ud83d\ud83d""#).is_err());
+    assert!(JsonValue::parse(r#""\ud83d\u0041""#).is_err());
+    assert!(JsonValue::parse(r#""\ud83d""#).is_err());
+    assert!(JsonValue::parse(r#""\ude00""#).is_err());
+    assert!(JsonValue::parse(r#""\u+041""#).is_err());
+    Ok(())
+}
+
+#[test]
+fn parse_file_list_works() {
+    let text = "src/foo.erl\n\n# comment\n  include/bar.hrl  \nrebar.config";
+    assert_eq!(
+        efmt::files::parse_file_list(text),
+        ["src/foo.erl", "include/bar.hrl", "rebar.config"]
+            .map(std::path::PathBuf::from)
+            .to_vec()
+    );
+    assert!(efmt::files::parse_file_list("\n# empty\n").is_empty());
+}
+
+#[test]
+fn backup_file_works() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("foo.erl");
+    std::fs::write(&path, "foo()->ok.\n")?;
+
+    let backup_path = efmt::files::backup_file(&path, ".orig", false)?;
+    assert_eq!(backup_path, Some(dir.path().join("foo.erl.orig")));
+    std::fs::write(&path, "foo() -> ok.\n")?;
+
+    // Existing backup files are kept unless `force` is `true`.
+    assert_eq!(efmt::files::backup_file(&path, ".orig", false)?, None);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("foo.erl.orig"))?,
+        "foo()->ok.\n"
+    );
+    efmt::files::backup_file(&path, ".orig", true)?;
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("foo.erl.orig"))?,
+        "foo() -> ok.\n"
+    );
+
+    assert!(efmt::files::backup_file(&path, "", false).is_err());
+    Ok(())
+}
+
+#[test]
+fn diff_stats_works() {
+    use efmt::diff::DiffStats;
+
+    let original = "-module(foo).\nfoo()->\n    ok.\nbar() -> ok.\n";
+    let formatted = "-module(foo).\n\nfoo() ->\n    ok.\n\nbar() -> ok.\n";
+    let mut stats = DiffStats::new(original, formatted);
+    assert_eq!(
+        stats,
+        DiffStats {
+            added_lines: 3,
+            removed_lines: 1,
+            original_bytes: original.len(),
+            formatted_bytes: formatted.len(),
+        }
+    );
+    assert_eq!(
+        DiffStats::new(original, original),
+        DiffStats {
+            original_bytes: original.len(),
+            formatted_bytes: original.len(),
+            ..DiffStats::default()
+        }
+    );
+
+    stats += stats;
+    assert_eq!((stats.added_lines, stats.removed_lines), (6, 2));
+}
+
+#[test]
+fn check_corpus_works() -> anyhow::Result<()> {
+    let report = efmt::testing::check_corpus("tests/testdata/")?;
+    assert!(report.checked_files > 0);
+    assert!(report.is_ok(), "{:?}", report.failures);
+    Ok(())
+}
+
+/// Runs [efmt::testing::check_corpus()] against the directory specified by `$EFMT_CORPUS_DIR` (e.g., a checkout of OTP).
+#[test]
+#[ignore]
+fn check_external_corpus() -> anyhow::Result<()> {
+    let dir = std::env::var("EFMT_CORPUS_DIR")?;
+    let report = efmt::testing::check_corpus(dir)?;
+    for failure in &report.failures {
+        eprintln!("{}: {}", failure.path.display(), failure.reason);
+    }
+    assert!(report.is_ok());
+    Ok(())
+}
@@ -0,0 +1,34 @@
+-module(conditional).
+
+-export([foo/0, bar/0]).
+
+-if(?OTP_RELEASE >= 25).
+
+
+foo() -> {new, json:encode(#{a => 1})}.
+
+
+-elif(?OTP_RELEASE >= 23 andalso ?OTP_RELEASE < 25).
+
+
+foo() -> {old, jsone:encode(#{a => 1})}.
+
+
+-else.
+
+
+foo() -> unsupported.
+
+
+-endif.
+
+-if(?OTP_RELEASE >= 25).
+-define(DEFAULT_TIMEOUT, 5000).
+-elif(defined(TEST)).
+-define(DEFAULT_TIMEOUT, infinity).
+-else.
+-define(DEFAULT_TIMEOUT, 1000).
+-endif.
+
+
+bar() -> ?DEFAULT_TIMEOUT.
@@ -0,0 +1,24 @@
+-module(same_name_specs).
+
+-export([f/1, f/2, g/0, g/1]).
+
+
+-spec f(integer()) -> integer().
+f(X) ->
+    f(X, 1).
+
+
+-spec f(integer(), integer()) ->
+          integer().
+f(X, Y) ->
+    X + Y.
+
+
+-spec g(atom()) -> atom().
+g(X) ->
+    X.
+
+
+-spec g() -> atom().
+g() ->
+    g(foo).